@@ -1,6 +1,7 @@
-use std::borrow::Borrow;
-
-use crate::coord_iter::coords_along;
+use crate::explore_2d::neighbors;
+use crate::space2d::pattern::Pattern;
+use crate::space2d::view::Transform;
+use crate::space2d::{Coord, Field, TableField};
 use aoc_runner_derive::{aoc, aoc_generator};
 
 type ParsedInput = Vec<Vec<char>>;
@@ -36,73 +37,64 @@ fn print(array: &Vec<Vec<char>>) {
     })
 }
 
-type Coord = (usize, usize);
-type Delta = (isize, isize);
-
-fn find_string(array: &Vec<Vec<char>>, s: &str, coords: Coord, delta: impl Borrow<Delta>) -> bool {
-    let coords = coords_along(coords, delta, s.len(), (array[0].len(), array.len()));
-    coords.len() == s.len()
-        && coords
-            .zip(s.chars())
-            .all(|(coord, value)| array[coord.1][coord.0] == value)
+fn find_string(field: &TableField<char>, s: &str, from: Coord, step: Coord) -> bool {
+    let found = field.ray(&from, step).take(s.len()).map(|(_, c)| *c);
+    found.eq(s.chars())
 }
 
 #[aoc(day4, part1)]
 fn solve(input: &ParsedInput) -> Result<usize, String> {
-    let h = input.len();
-    let w = input[0].len();
-
-    let dir: Vec<(isize, isize)> = vec![
-        (0, 1),
-        (1, 1),
-        (1, 0),
-        (1, -1),
-        (0, -1),
-        (-1, -1),
-        (-1, 0),
-        (-1, 1),
-    ];
-
-    let search = "XMAS";
-    let mut occurence = 0;
-    for y in 0..h {
-        for x in 0..w {
-            occurence += dir
+    let field = TableField::from_rows(input.clone()).map_err(|_| "Ragged input")?;
+    let occurence = field
+        .definition_area()
+        .iter()
+        .map(|from| {
+            neighbors::EIGHT
                 .iter()
-                .filter(|delta| find_string(&input, &search, (x, y), *delta))
-                .count();
-        }
-    }
+                .filter(|step| find_string(&field, "XMAS", from, **step))
+                .count()
+        })
+        .sum();
     Ok(occurence)
 }
 
-static RIGHT_UP: Delta = (1, 1);
-static LEFT_UP: Delta = (-1, 1);
-static LEFT_DOWN: Delta = (-1, -1);
-static RIGHT_DOWN: Delta = (1, -1);
-
-static DIAG_VALUES: [&'static str; 4] = ["AM", "AM", "AS", "AS"];
-static CONSECUTIVE_DIAGS: [Delta; 4] = [RIGHT_UP, LEFT_UP, LEFT_DOWN, RIGHT_DOWN];
-
-fn cross_mas(array: &Vec<Vec<char>>, coords: Coord, first_mas_ind: usize) -> bool {
-    (0..4)
-        .map(|i| CONSECUTIVE_DIAGS[(i + first_mas_ind) % 4]) // Modulo magic to cycle through diagonals
-        .zip(DIAG_VALUES.iter())
-        .all(|(diag, value)| find_string(array, value, coords, diag))
-}
-
 #[aoc(day4, part2)]
 fn count_cross_mas(input: &ParsedInput) -> Result<usize, String> {
-    let h = input.len();
-    let w = input[0].len();
+    let cross = Pattern::from_text("M.S\n.A.\nM.S", |c| (c != '.').then_some(c))
+        .map_err(|_| "Invalid pattern")?;
+    let field = TableField::from_rows(input.clone()).map_err(|_| "Ragged input")?;
+    Ok(cross.matches(&field, &Transform::ROTATIONS).count())
+}
 
-    let mut occurence = 0;
-    for y in 0..h {
-        for x in 0..w {
-            occurence += (0..4)
-                .filter(|starting_diag| cross_mas(&input, (x, y), *starting_diag))
-                .count();
+#[cfg(test)]
+mod tests {
+    use crate::day04::{count_cross_mas, parse_whatever, solve};
+    use indoc::indoc;
+
+    fn example() -> &'static str {
+        indoc! {"
+            MMMSXXMASM
+            MSAMXMSMSA
+            AMXSXMAAMM
+            MSAMASMSMX
+            XMASAMXAMM
+            XXAMMXXAMA
+            SMSMSASXSS
+            SAXAMASAAA
+            MAMMMXMMMM
+            MXMXAXMASX"
         }
     }
-    Ok(occurence)
+
+    #[test]
+    fn it_counts_xmas_in_every_direction() {
+        let grid = parse_whatever(example());
+        assert_eq!(solve(&grid), Ok(18));
+    }
+
+    #[test]
+    fn it_counts_crossed_mas() {
+        let grid = parse_whatever(example());
+        assert_eq!(count_cross_mas(&grid), Ok(9));
+    }
 }
@@ -3,53 +3,40 @@ use std::collections::HashSet;
 use aoc_runner_derive::{aoc, aoc_generator};
 use eyre::Report;
 
-use itertools::{Either, Itertools};
+use itertools::Itertools;
 
-use crate::space2d::{moved, Coord, Direction};
+use crate::functional::brent;
+use crate::space2d::bits::BitField;
+use crate::space2d::load::CharGrid;
+use crate::space2d::{moved, Coord, Direction, Field};
 
-type ParsedInput = (HashSet<Coord>, Coord, Option<(Coord, Direction)>);
-
-
-#[derive(Copy, Clone)]
-enum Item {
-    Obstacle,
-    Guard(Direction),
-}
+// Tells whether there is an obstacle on each position of the lab
+type Obstacles = BitField;
+type ParsedInput = (Obstacles, Option<(Coord, Direction)>);
 
 #[aoc_generator(day6)]
 fn parse_day6(input: &str) -> Result<ParsedInput, Report> {
-    let lines = input.lines().collect::<Vec<_>>();
-    let w = lines[0].len();
-    let h = lines.len();
-    let map = input
-        .lines()
-        .enumerate()
-        .map(|(y, line)| {
-            line.chars().enumerate().filter_map(move |(x, c)| {
-                match c {
-                    '#' => Some(Item::Obstacle),
-                    '^' => Some(Item::Guard(Direction::Up)),
-                    '>' => Some(Item::Guard(Direction::Ri)),
-                    'v' => Some(Item::Guard(Direction::Do)),
-                    '<' => Some(Item::Guard(Direction::Le)),
-                    _ => None,
-                }
-                .map(|item| (Coord(x as isize, y as isize), item))
-            })
-        })
-        .flatten()
-        .collect::<Vec<_>>();
-
-    let (ground, mut guard): (Vec<_>, Vec<_>) =
-        map.into_iter().partition_map(|(coord, item)| match item {
-            Item::Obstacle => Either::Left(coord),
-            Item::Guard(d) => Either::Right((coord, d)),
-        });
+    let lab = CharGrid::new()
+        .cell('.', false)
+        .cell('#', true)
+        .entity('^', "guard ^", false)
+        .entity('>', "guard >", false)
+        .entity('v', "guard v", false)
+        .entity('<', "guard <", false)
+        .load(input)?;
+
+    let guard = [
+        ("guard ^", Direction::Up),
+        ("guard >", Direction::Ri),
+        ("guard v", Direction::Do),
+        ("guard <", Direction::Le),
+    ]
+    .into_iter()
+    .find_map(|(name, dir)| Some((lab.entity(name)?, dir)));
 
     Ok((
-        ground.into_iter().collect(),
-        Coord(w as isize, h as isize),
-        guard.pop(),
+        Obstacles::from_field(&lab.field, |obstacle| *obstacle),
+        guard,
     ))
 }
 
@@ -57,7 +44,6 @@ fn parse_day6(input: &str) -> Result<ParsedInput, Report> {
 struct GuardPatrol {
     // Set of positions visited with each direction of guard while visiting each of them
     visited: HashSet<(Coord, Direction)>,
-    is_looping: bool,
 }
 
 impl GuardPatrol {
@@ -69,25 +55,15 @@ impl GuardPatrol {
     }
 }
 
-trait Field {
-    fn defined_on(&self, coord: &Coord) -> bool;
-}
-
-impl Field for Coord {
-    fn defined_on(&self, coord: &Coord) -> bool {
-        (0..self.0).contains(&coord.0) && (1..self.1).contains(&coord.1)
-    }
-}
-
 fn move_until_loop<F: Field, S: PartialEq + Eq + std::hash::Hash>(
-    field: F,
+    field: &F,
     init: (Coord, S),
     move_fct: impl Fn(&(Coord, S)) -> (Coord, S),
 ) -> HashSet<(Coord, S)> {
     let mut visited = HashSet::<(Coord, S)>::new();
     let mut looping = false;
     let mut state = init;
-    while !looping && field.defined_on(&state.0) {
+    while !looping && field.definition_area().contains(&state.0) {
         let new_state = move_fct(&state);
         looping = !visited.insert(state);
         state = new_state;
@@ -95,52 +71,42 @@ fn move_until_loop<F: Field, S: PartialEq + Eq + std::hash::Hash>(
     visited
 }
 
-fn run_guard(
-    obstacles: &HashSet<Coord>,
-    size: &Coord,
-    (mut guard_pos, mut guard_dir): (Coord, Direction),
-) -> GuardPatrol {
-    move_until_loop(
-        *size,
-        (guard_pos, guard_dir),
-        |(mut coord, mut delta): &(Coord, Direction)| {
-            let toward = moved(&coord, &delta);
-            if obstacles.contains(&toward) {
-                delta = delta.rotate();
-            } else {
-                coord = toward;
-            }
-            (coord, delta)
-        },
-    );
-
-    let mut visited = HashSet::<(Coord, Direction)>::new();
-    let mut is_looping = false;
-    while (0isize..(size.0) as isize).contains(&guard_pos.0)
-        && (0isize..size.1 as isize).contains(&guard_pos.1)
-        && !is_looping
-    {
-        is_looping = !visited.insert((guard_pos, guard_dir));
-        let new_guard_pos = moved(&guard_pos, &guard_dir);
-        if obstacles.contains(&new_guard_pos) {
-            guard_dir = guard_dir.rotate()
+fn run_guard(obstacles: &Obstacles, guard: (Coord, Direction)) -> GuardPatrol {
+    let visited = move_until_loop(obstacles, guard, |(coord, dir): &(Coord, Direction)| {
+        let toward = moved(coord, dir);
+        if obstacles.get(&toward) == Some(&true) {
+            (*coord, dir.rotate())
         } else {
-            guard_pos = new_guard_pos;
+            (toward, *dir)
         }
-    }
+    });
 
-    GuardPatrol {
-        visited,
-        is_looping,
-    }
+    GuardPatrol { visited }
+}
+
+// Guard state on its next turn, or None once it left the area
+fn guard_jump(
+    obstacles: &Obstacles,
+    (guard_pos, guard_dir): (Coord, Direction),
+) -> Option<(Coord, Direction)> {
+    let obstacle = obstacles.next_set(&guard_pos, &guard_dir)?;
+    let before_obstacle = moved(&obstacle, &guard_dir.rotate().rotate());
+    Some((before_obstacle, guard_dir.rotate()))
+}
+
+// Leaving the area ends on the `None` fixed point, any other cycle means the guard is looping
+fn is_guard_looping(obstacles: &Obstacles, guard: (Coord, Direction)) -> bool {
+    brent(Some(guard), |state| {
+        state.and_then(|state| guard_jump(obstacles, state))
+    })
+    .entry
+    .is_some()
 }
 
 #[aoc(day6, part1)]
 fn solve_part1(map: &ParsedInput) -> Result<usize, String> {
-    if let (map, size, Some(guard)) = map.clone() {
-        Ok(run_guard(&map, &size, guard)
-            .into_visited_position()
-            .count())
+    if let (obstacles, Some(guard)) = map {
+        Ok(run_guard(obstacles, *guard).into_visited_position().count())
     } else {
         Ok(0)
     }
@@ -148,19 +114,19 @@ fn solve_part1(map: &ParsedInput) -> Result<usize, String> {
 
 #[aoc(day6, part2)]
 fn solve_part2(map: &ParsedInput) -> Result<usize, String> {
-    if let (mut obstacles, size, Some(guard)) = map.clone() {
-        let visited = run_guard(&obstacles, &size, guard)
+    if let (mut obstacles, Some(guard)) = map.clone() {
+        let visited = run_guard(&obstacles, guard)
             .into_visited_position()
-            .skip(1); // We can't put the obstacle at the guard first position
+            .filter(|coord| *coord != guard.0); // We can't put the obstacle at the guard first position
         Ok(visited
             .filter(|coord| {
-                if !obstacles.contains(coord) {
-                    obstacles.insert(*coord);
-                    let GuardPatrol { is_looping, .. } = run_guard(&obstacles, &size, guard);
-                    obstacles.remove(coord);
+                if obstacles.get(coord) != Some(&true) {
+                    obstacles.set(coord, true);
+                    let is_looping = is_guard_looping(&obstacles, guard);
+                    obstacles.set(coord, false);
                     is_looping
                 } else {
-                    run_guard(&obstacles, &size, guard).is_looping
+                    is_guard_looping(&obstacles, guard)
                 }
             })
             .count())
@@ -168,3 +134,36 @@ fn solve_part2(map: &ParsedInput) -> Result<usize, String> {
         Ok(0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::day06::{parse_day6, solve_part1, solve_part2};
+    use indoc::indoc;
+
+    fn example() -> &'static str {
+        indoc! {"
+            ....#.....
+            .........#
+            ..........
+            ..#.......
+            .......#..
+            ..........
+            .#..^.....
+            ........#.
+            #.........
+            ......#..."
+        }
+    }
+
+    #[test]
+    fn it_counts_positions_visited_by_the_guard() {
+        let map = parse_day6(example()).unwrap();
+        assert_eq!(solve_part1(&map), Ok(41));
+    }
+
+    #[test]
+    fn it_counts_obstructions_trapping_the_guard() {
+        let map = parse_day6(example()).unwrap();
+        assert_eq!(solve_part2(&map), Ok(6));
+    }
+}
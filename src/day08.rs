@@ -47,19 +47,16 @@ fn solve_part1(input: &ParsedInput) -> Result<usize, String> {
                 //.map(|(x, y)| Coords(*x, *y))
                 .tuple_combinations()
                 .map(|couple: (Coord, Coord)| {
-                    vec![
-                        couple.0 + (couple.0 - couple.1),
-                        couple.1 + (couple.1 - couple.0),
-                    ]
-                    .into_iter()
-                    .filter(|coord| input.0.contains(&coord))
-                    .inspect(|coord| {
-                        println!(
-                            "{}: {:?} - {:?} node at {:?}",
-                            *antenna, couple.0, couple.1, *coord
-                        )
-                    })
-                    .collect::<Vec<_>>()
+                    vec![couple.0 * 2 - couple.1, couple.1 * 2 - couple.0]
+                        .into_iter()
+                        .filter(|coord| input.0.contains(&coord))
+                        .inspect(|coord| {
+                            println!(
+                                "{}: {:?} - {:?} node at {:?}",
+                                *antenna, couple.0, couple.1, *coord
+                            )
+                        })
+                        .collect::<Vec<_>>()
                 })
                 .flatten()
         })
@@ -80,14 +77,8 @@ fn solve_part2(input: &ParsedInput) -> Result<usize, String> {
                 //.map(|(x, y)| Coords(*x, *y))
                 .tuple_combinations()
                 .map(|couple: (Coord, Coord)| {
-                    let one_dir = std::iter::successors(Some(couple.0), |coord| {
-                        Some(*coord + (couple.0 - couple.1))
-                    })
-                    .take_while(|coord| input.0.contains(coord));
-                    let snd_dir = std::iter::successors(Some(couple.1), |coord| {
-                        Some(*coord + (couple.1 - couple.0))
-                    })
-                    .take_while(|coord| input.0.contains(coord));
+                    let one_dir = input.0.ray(couple.0, couple.0 - couple.1);
+                    let snd_dir = input.0.ray(couple.1, couple.1 - couple.0);
                     one_dir
                         .chain(snd_dir)
                         .inspect(|coord| {
@@ -91,7 +91,9 @@ fn solve_part1_use_f(input: &ParsedInput) -> Result<usize, String> {
             disk
         },
         |disk| disk.clone(),
-    );
+        None,
+    )
+    .map_err(|_| "Disk never stopped changing".to_string())?;
 
     Ok(compute_checksum(&compressed))
 }
@@ -1,10 +1,7 @@
-use itertools::Itertools;
 use std::collections::HashSet;
 
-use crate::explore_2d::{neighbors, Exploration, Explore, Gather};
+use crate::explore_2d::{neighbors, Exploration, Explore, Gather, NodeGather};
 
-use crate::space2d::Field;
-use crate::tree_reduce::TreeReduceCompute;
 use aoc_runner_derive::{aoc, aoc_generator};
 use eyre::Report;
 
@@ -51,6 +48,8 @@ impl<T> Gather<T> for CountSummit {
     }
 }
 
+impl<T> NodeGather<T> for CountSummit {}
+
 type ParsedInput = TableField<usize>;
 
 use eyre::eyre;
@@ -67,16 +66,13 @@ pub fn parse_day10(input: &str) -> Result<ParsedInput, Report> {
 
 #[aoc(day10, part1, explore_2d)]
 fn solve_part1_explore_2d(input: &ParsedInput) -> Result<usize, Report> {
-    let bb = input.definition_area();
-
-    let exploration = Exploration::new(input, Hiker {}, CountSummit {});
+    let exploration = Exploration::new(input, Hiker {}, CountSummit {}).memoised();
 
-    let res = (0..bb.ymax)
-        .into_iter()
-        .cartesian_product((0..bb.xmax).into_iter())
-        .map(|(y, x)| Coord(x, y))
-        .filter(|c| input.get(&c) == Some(&0))
-        .map(|c| exploration.compute(&(c, 0), 1).len())
+    let res = exploration
+        .from_all(|height| *height == 0)
+        .per_source
+        .iter()
+        .map(|(_, summits)| summits.len())
         .sum();
 
     Ok(res)
@@ -96,19 +92,56 @@ impl<T> Gather<T> for CountPath {
     }
 }
 
+impl<T> NodeGather<T> for CountPath {}
+
 #[aoc(day10, part2, explore_2d)]
 fn solve_part2_explore(input: &ParsedInput) -> Result<usize, Report> {
-    let bb = input.definition_area();
+    let exploration = Exploration::new(input, Hiker {}, CountPath {}).memoised();
 
-    let exploration = Exploration::new(input, Hiker {}, CountPath {});
-
-    let res = (0..bb.ymax)
-        .into_iter()
-        .cartesian_product((0..bb.xmax).into_iter())
-        .map(|(y, x)| Coord(x, y))
-        .filter(|c| input.get(&c) == Some(&0))
-        .map(|c| exploration.compute(&(c, 0), 1))
-        .sum();
+    let res = exploration.from_all(|height| *height == 0).total;
 
     Ok(res)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::day10::{parse_day10, solve_part1_explore_2d, solve_part2_explore};
+    use crate::day10::{CountPath, CountSummit, Hiker};
+    use crate::explore_2d::Exploration;
+    use crate::space2d::Field;
+    use indoc::indoc;
+
+    fn example() -> &'static str {
+        indoc! {"
+            89010123
+            78121874
+            87430965
+            96549874
+            45678903
+            32019012
+            01329801
+            10456732"
+        }
+    }
+
+    #[test]
+    fn it_scores_and_rates_trailheads() {
+        let map = parse_day10(example()).unwrap();
+        assert_eq!(solve_part1_explore_2d(&map).unwrap(), 36);
+        assert_eq!(solve_part2_explore(&map).unwrap(), 81);
+    }
+
+    #[test]
+    fn memoised_exploration_matches_plain_one() {
+        let map = parse_day10(example()).unwrap();
+        let summits = Exploration::new(&map, Hiker {}, CountSummit {});
+        let memoised_summits = Exploration::new(&map, Hiker {}, CountSummit {}).memoised();
+        let paths = Exploration::new(&map, Hiker {}, CountPath {});
+        let memoised_paths = Exploration::new(&map, Hiker {}, CountPath {}).memoised();
+        for trailhead in map.positions(|height| *height == 0) {
+            let start = (trailhead, 0);
+            assert_eq!(summits.explore(&start), memoised_summits.explore(&start));
+            assert_eq!(paths.explore(&start), memoised_paths.explore(&start));
+        }
+    }
+}
@@ -52,16 +52,13 @@ use eyre::Result;
 
 use crate::day10::parse_day10;
 
-use itertools::Itertools;
 #[aoc(day10, part1, original)]
 fn solve_part1(input: &ParsedInput) -> Result<usize, Report> {
     let bb = input.definition_area();
 
     let hk = HikingPath { field: input };
-    let res = (0..bb.ymax)
-        .into_iter()
-        .cartesian_product((0..bb.xmax).into_iter())
-        .map(|(y, x)| Coord(x, y))
+    let res = bb
+        .iter()
         .filter(|c| hk.field.get(&c) == Some(&0))
         .map(|c| hk.compute(&c, 1).len())
         .sum();
@@ -110,10 +107,8 @@ fn solve_part2(input: &ParsedInput) -> Result<usize, Report> {
     let bb = input.definition_area();
     let hk = HikingPathNumber { field: input };
 
-    let res = (0..bb.ymax)
-        .into_iter()
-        .cartesian_product((0..bb.xmax).into_iter())
-        .map(|(y, x)| Coord(x, y))
+    let res = bb
+        .iter()
         .filter(|c| hk.field.get(&c) == Some(&0))
         .map(|c| hk.compute(&c, 1))
         .sum();
@@ -2,206 +2,55 @@ use eyre::eyre;
 use itertools::Itertools;
 use std::collections::HashMap;
 
-use crate::space2d::{BoundingBox, Field};
 use aoc_runner_derive::{aoc, aoc_generator};
 use eyre::Report;
 
 use huparse::parse::Parse;
 use huparse::parser;
 
-use crate::space2d::{Coord, TableField};
+use crate::explore_2d::neighbors;
+use crate::regions::{components, Regions};
+use crate::space2d::render::Render;
+use crate::space2d::TableField;
 
 type ParsedInput = TableField<char>;
 
-fn get_connexe(
-    coord: &Coord,
-    tag: usize,
-    ground: &TableField<char>,
-    connexe_map: &mut HashMap<Coord, usize>,
-    fences_per_tag: &mut HashMap<usize, Vec<Coord>>,
-) {
-    // Connexes parts
-    let child = [Coord(1, 0), Coord(0, 1), Coord(-1, 0), Coord(0, -1)]
-        .into_iter()
-        .map(|d| (*coord + d))
-        .filter(|nc| ground.definition_area().contains(&nc))
-        .filter(|nc| !connexe_map.contains_key(&nc))
-        .filter(|nc| ground.get(&nc) == ground.get(coord))
-        .collect::<Vec<_>>();
-
-    // Here is the trick
-    // The space is mapped to a 3 timers bigger space
-    // - Previous cells are mapped to 3*x+2,3*y+2 coordinates
-    // - Each fence has two corresponding value in the mapped space
-    //   - One for each tag on its left/right side, if the fence is vertical
-    //   - One for each tag on its bottom/top side, if the fence is horizontal
-    // So the maps looks like
-    //
-    //    ┴  ┴  ┴
-    //    ┬  ┬  ┬
-    //  ┤├A┤├B  B┤├
-    //          ┴
-    //          ┬
-    //  ┤├A┤├B  C┤├
-    //
-    // where:
-    //  - ├  is right side of fence
-    //
-    //  - ┤  is left side of fence
-    //
-    //  - ┬  is down side of fence
-    //
-    //  - ┴  is top side of fence
-    //
-    let out_fences = [Coord(1, 0), Coord(0, 1), Coord(-1, 0), Coord(0, -1)]
-        .into_iter()
-        .filter(|d| !ground.definition_area().contains(&(*coord + *d)))
-        .map(|d| *coord + *coord + *coord + d + Coord(2, 2))
-        .collect();
-
-    let fences = [Coord(1, 0), Coord(0, 1), Coord(-1, 0), Coord(0, -1)]
-        .into_iter()
-        .filter(|d| ground.definition_area().contains(&(*coord + *d)))
-        .filter(|d| ground.get(&(*coord + *d)) != ground.get(coord))
-        .map(|d| *coord + *coord + *coord + d + Coord(2, 2))
-        .collect();
-
-    // update fence coords per tag
-    let new_count = [
-        fences_per_tag
-            .remove_entry(&tag)
-            .map(|(_, v)| v)
-            .unwrap_or_default(),
-        fences,
-        out_fences,
-    ]
-    .concat();
-    fences_per_tag.insert(tag, new_count);
-
-    // Update connexity map and recurse
-    child.iter().for_each(|c| {
-        connexe_map.insert(*c, tag);
-    });
-    child
-        .iter()
-        .for_each(|c| get_connexe(c, tag, ground, connexe_map, fences_per_tag));
-}
-
 #[allow(dead_code)]
-fn display_garden_with_fences(
-    garden: &TableField<char>,
-    fencing: &HashMap<Coord, usize>,
-    bb: &BoundingBox,
-) {
-    for y in 0..bb.ymax * 3 + 1 {
-        for x in 0..bb.xmax * 3 + 1 {
-            if let Some(tag) = fencing.get(&Coord(x, y)) {
-                print!("{tag}")
-            } else if x % 3 == 2 && y % 3 == 2 {
-                print!(
-                    "{}",
-                    *garden.get(&Coord((x - 2) / 3, (y - 2) / 3)).unwrap_or(&'?')
-                );
-            } else {
-                print!(" ");
-            }
-        }
-        println!("");
-    }
+fn display_garden_with_fences(garden: &TableField<char>) -> String {
+    let details = GardenDetails::compute_from(garden);
+    let fences = (0..details.regions.len())
+        .flat_map(|tag| details.regions.geometry(tag).perimeter)
+        .map(|edge| (edge.cell, edge.facing()))
+        .collect_vec();
+    Render::new(garden, |_, plant| *plant)
+        .fences(fences)
+        .render()
 }
 
 struct GardenDetails {
-    tag_per_coord: HashMap<Coord, usize>,
-    bounding_box: BoundingBox,
-    fences_per_tag: HashMap<usize, Vec<Coord>>,
+    regions: Regions,
 }
 
 impl GardenDetails {
     fn compute_from(garden: &TableField<char>) -> Self {
-        let mut connexe = HashMap::<Coord, usize>::new();
-        let mut fencing = HashMap::<usize, Vec<Coord>>::new();
-        let mut grp = 0;
-
-        // Fill connexity map starting from each cell
-        let bb = garden.definition_area();
-        for y in bb.ymin..bb.ymax {
-            for x in bb.xmin..bb.xmax {
-                let c = Coord(x, y);
-                if !connexe.contains_key(&c) {
-                    connexe.insert(c, grp);
-                    get_connexe(&c, grp, garden, &mut connexe, &mut fencing);
-                    grp += 1;
-                }
-            }
-        }
+        let regions = components(garden, &neighbors::FOUR, |(a, b): (&char, &char)| a == b);
+        GardenDetails { regions }
+    }
 
-        GardenDetails {
-            tag_per_coord: connexe,
-            bounding_box: *bb,
-            fences_per_tag: fencing,
-        }
+    fn area_per_region(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.regions.len()).map(|tag| (tag, self.regions.stats(tag).area))
     }
 
-    fn area_per_region<'a>(&'a self) -> impl Iterator<Item = (usize, usize)> + 'a {
-        self.tag_per_coord
-            .iter()
-            .map(|(coord, tag)| (tag, coord))
-            .into_group_map()
-            .into_iter()
-            .map(|(tag, coords)| (*tag, coords.len()))
+    fn perimeter_per_region(&self) -> HashMap<usize, usize> {
+        (0..self.regions.len())
+            .map(|tag| (tag, self.regions.geometry(tag).perimeter.len()))
+            .collect()
     }
 
     fn sides_per_region(&self) -> HashMap<usize, usize> {
-
-        // Generate a map of fences side tag
-        let fencing: HashMap<_, _> = self
-            .fences_per_tag
-            .iter()
-            .map(|(tag, coords)| coords.into_iter().map(move |c| (c, *tag)))
-            .flatten()
-            .collect();
-
-        // Count, per tag, number of consecutives fence part (with same tag) for each (columns,side)
-        let mut region_sides = HashMap::<usize, usize>::new();
-        for x in 0..(self.bounding_box.xmax * 3 + 2) {
-            if x % 3 != 2 {
-                let col_side_counts = (0..self.bounding_box.ymax)
-                    .map(|y| fencing.get(&Coord(x, y * 3 + 2)))
-                    .dedup_by(|t1, t2| match (t1, t2) {
-                        (None, None) => true,
-                        (Some(t1), Some(t2)) => t1 == t2,
-                        _ => false,
-                    })
-                    .flatten()
-                    .counts();
-                col_side_counts.iter().for_each(|(tag, count)| {
-                    let new_count =
-                        region_sides.remove_entry(*tag).map(|x| x.1).unwrap_or(0) + count;
-                    region_sides.insert(**tag, new_count);
-                });
-            }
-        }
-
-        // Count, per tag, number of consecutives fence part (with same tag) for each (row,side)
-        for y in 0..(self.bounding_box.ymax * 3 + 2) {
-            if y % 3 != 2 {
-                let row_side_counts = (0..self.bounding_box.ymax)
-                    .map(|x| fencing.get(&Coord(x * 3 + 2, y)))
-                    .dedup_by(|t1, t2| match (t1, t2) {
-                        (None, None) => true,
-                        (Some(t1), Some(t2)) => t1 == t2,
-                        _ => false,
-                    })
-                    .flatten()
-                    .counts();
-                row_side_counts.iter().for_each(|(tag, count)| {
-                    let new_count =
-                        region_sides.remove_entry(*tag).map(|x| x.1).unwrap_or(0) + count;
-                    region_sides.insert(**tag, new_count);
-                });
-            }
-        }
-        region_sides
+        (0..self.regions.len())
+            .map(|tag| (tag, self.regions.geometry(tag).sides))
+            .collect()
     }
 }
 
@@ -216,10 +65,12 @@ fn parse_garden(input: &str) -> Result<ParsedInput, Report> {
 fn price_for_fences(input: &ParsedInput) -> Result<usize, String> {
     let garden_details = GardenDetails::compute_from(&input);
 
+    let region_perimeters = garden_details.perimeter_per_region();
+
     // Compute part perimeter * part area
     let res = garden_details
         .area_per_region()
-        .map(|(tag, area)| garden_details.fences_per_tag[&tag].len() * area)
+        .map(|(tag, area)| region_perimeters[&tag] * area)
         .sum();
 
     Ok(res)
@@ -245,6 +96,7 @@ mod tests {
 
     mod it_computes {
         use crate::day12::*;
+        use crate::space2d::Coord;
         use indoc::indoc;
         #[test]
         fn price_for_one_cell_garden() {
@@ -366,9 +218,12 @@ mod tests {
             let garden = parse_garden(&garden).unwrap();
             let details = GardenDetails::compute_from(&garden);
 
-            let expected_sides = [(Coord(0, 0), 11), (Coord(3, 1), 4), (Coord(1, 3), 4)];
+            // Outer border and both inner squares: 28 * 12 + 4 * 4 + 4 * 4 = 368
+            let expected_sides = [(Coord(0, 0), 12), (Coord(3, 1), 4), (Coord(1, 3), 4)];
             expected_sides.iter().for_each(|(c, expected)| {
-                let tag = details.tag_per_coord.get(&c).unwrap();
+                let tag = &details.regions.label_of(c).unwrap();
+                let geometry = details.regions.geometry(*tag);
+                assert_eq!(geometry.sides, geometry.corners.total());
                 assert_eq!(
                     details.sides_per_region().get(tag).unwrap(),
                     expected,
@@ -4,10 +4,28 @@ use aoc_runner_derive::{aoc, aoc_generator};
 use eyre::Report;
 use huparse::{parse::Parse, parser};
 
+use crate::functional::brent;
+use crate::space2d::pattern::Pattern;
+use crate::space2d::render::render_with;
+use crate::space2d::view::Transform;
+use crate::space2d::{BoundingBox, Coord, FieldMut, TableField};
+
 type ParsedInput = Vec<(i32, i32, i32, i32)>;
 
 static W: i32 = 101;
 static H: i32 = 103;
+static AREA: BoundingBox = BoundingBox {
+    xmin: 0,
+    xmax: W as isize,
+    ymin: 0,
+    ymax: H as isize,
+};
+
+// Position of the robot after `time` seconds
+fn robot_at(&(px, py, vx, vy): &(i32, i32, i32, i32), time: i32) -> (i32, i32) {
+    let Coord(x, y) = AREA.wrap(&Coord((px + vx * time) as isize, (py + vy * time) as isize));
+    (x as i32, y as i32)
+}
 
 #[aoc_generator(day14)]
 fn parse_day14(input: &str) -> Result<ParsedInput, Report> {
@@ -19,7 +37,7 @@ fn parse_day14(input: &str) -> Result<ParsedInput, Report> {
 fn solve_part1(input: &ParsedInput) -> i32 {
     input
         .iter()
-        .map(|(px, py, vx, vy)| ((px + vx * 100).rem_euclid(W), (py + vy * 100).rem_euclid(H)))
+        .map(|robot| robot_at(robot, 100))
         .filter(|(x, y)| *x != W / 2 && *y != H / 2)
         .fold([0, 0, 0, 0], |mut acc, (x, y)| {
             acc[(x / ((W + 1) / 2) + y / ((H + 1) / 2) * 2) as usize] += 1;
@@ -29,29 +47,79 @@ fn solve_part1(input: &ParsedInput) -> i32 {
         .product()
 }
 
+fn set_all(map: &mut TableField<bool>, coords: &[Coord], value: bool) {
+    for coord in coords {
+        if let Some(cell) = map.get_mut(coord) {
+            *cell = value;
+        }
+    }
+}
+
 #[aoc(day14, part2)]
 fn solve_part2(input: &ParsedInput) -> Result<i32, String> {
-    let tree_pattern = std::iter::once((2, 0)) //    ..*..
-        .chain((1..=3).zip(std::iter::repeat(1))) // .***.
-        .chain((0..=4).zip(std::iter::repeat(2))) // *****
-        .collect_vec();
+    let tree = Pattern::from_text(
+        "..*..\n\
+         .***.\n\
+         *****",
+        |c| (c == '*').then_some(true),
+    )
+    .map_err(|_| "Invalid tree pattern")?;
 
-    for inc in 0..(W * H) {
-        let mut map = vec![[vec!['.'; W as usize], vec!['\n']].concat(); H as usize];
+    // Robots come back to their initial positions after this period, no need to look further
+    let period = brent(input.clone(), |robots| {
+        robots
+            .iter()
+            .map(|robot @ (_, _, vx, vy)| {
+                let (x, y) = robot_at(robot, 1);
+                (x, y, *vx, *vy)
+            })
+            .collect_vec()
+    })
+    .length as i32;
 
-        input
+    // Only cells holding a robot are updated from one second to the next
+    let mut map = TableField::filled(AREA, false);
+    for inc in 0..period {
+        let robots = input
             .iter()
-            .map(|(px, py, vx, vy)| ((px + vx * inc).rem_euclid(W), (py + vy * inc).rem_euclid(H)))
-            .for_each(|(x, y)| map[y as usize][x as usize] = '*');
-
-        for (y, x) in (0..map.len() - 2).cartesian_product(0..map[0].len() - 4 - 1) {
-            if tree_pattern.iter().all(|e| map[y + e.1][x + e.0] == '*') {
-                for (y, x) in (0..map.len()).cartesian_product(0..map[0].len()) {
-                    print!("{}", map[y][x]); // You need visual check, right ?
-                }
-                return Ok(inc);
-            }
+            .map(|robot| robot_at(robot, inc))
+            .map(|(x, y)| Coord(x as isize, y as isize))
+            .collect_vec();
+        set_all(&mut map, &robots, true);
+
+        if tree.matches(&map, &[Transform::Identity]).next().is_some() {
+            // You need visual check, right ?
+            print!(
+                "{}",
+                render_with(&map, |_, robot| if *robot { '*' } else { '.' })
+            );
+            return Ok(inc);
         }
+        set_all(&mut map, &robots, false);
     }
     Err("No tree found".into())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::day14::solve_part2;
+
+    #[test]
+    fn it_finds_the_first_second_showing_a_tree() {
+        //   ..*..
+        //   .***.
+        //   *****
+        let tree = std::iter::once((12, 20))
+            .chain((11..14).map(|x| (x, 21)))
+            .chain((10..15).map(|x| (x, 22)));
+        // Robots scattered everywhere but after 3 seconds
+        let robots = tree
+            .enumerate()
+            .map(|(i, (x, y))| {
+                let (vx, vy) = (i as i32 + 1, 2 * i as i32 - 7);
+                (x - 3 * vx, y - 3 * vy, vx, vy)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(solve_part2(&robots), Ok(3));
+    }
+}
@@ -1,88 +1,137 @@
 use aoc_runner_derive::{aoc, aoc_generator};
-use itertools::Itertools;
+use eyre::{eyre, Report};
 
-macro_rules! twod {
-    ($map:ident[$coord:expr]) => {
-        $map[$coord.1][$coord.0]
-    };
-}
+use crate::space2d::load::CharGrid;
+use crate::space2d::view::Scaled;
+use crate::space2d::{Coord, Field, FieldMut, TableField};
+
+// Walls, boxes and free cells, the robot being kept aside
+type Warehouse = TableField<char>;
+type ParsedInput = (Warehouse, Option<Coord>, String);
 
-type Coord = (usize, usize);
-static LEFT: fn(Coord) -> (usize, usize) = |(x, y)| (x - 1, y);
-static RIGHT: fn(Coord) -> (usize, usize) = |(x, y)| (x + 1, y);
+static LEFT: Coord = Coord(-1, 0);
+static RIGHT: Coord = Coord(1, 0);
 
-fn as_move(d: char) -> fn(Coord) -> Coord {
+fn as_move(d: char) -> Coord {
     match d {
         '<' => LEFT,
         '>' => RIGHT,
-        'v' => |(x, y)| (x, y + 1),
-        '^' => |(x, y)| (x, y - 1),
+        'v' => Coord(0, 1),
+        '^' => Coord(0, -1),
         _ => unreachable!(),
     }
 }
 
-fn do_move(c: Coord, dc: fn(Coord) -> Coord, map: &mut Vec<Vec<char>>, act: bool) -> bool {
-    let can_move = match (twod!(map[dc(c)]), dc((0, 0))) {
-        ('.', _) => true,
-        ('#', _) => false,
-        ('[', (_, 0)) | (']', (_, 0)) | ('@', _) | ('O', _) => do_move(dc(c), dc, map, act),
-        ('[', (0, _)) => do_move(dc(c), dc, map, act) && do_move(dc(RIGHT(c)), dc, map, act),
-        (']', (0, _)) => do_move(dc(c), dc, map, act) && do_move(dc(LEFT(c)), dc, map, act),
+fn do_move(c: Coord, dc: Coord, map: &mut Warehouse, act: bool) -> bool {
+    let can_move = match (map.get(&(c + dc)), dc) {
+        (Some('.'), _) => true,
+        (Some('#'), _) | (None, _) => false,
+        (Some('['), Coord(_, 0)) | (Some(']'), Coord(_, 0)) | (Some('O'), _) => {
+            do_move(c + dc, dc, map, act)
+        }
+        (Some('['), Coord(0, _)) => {
+            do_move(c + dc, dc, map, act) && do_move(c + dc + RIGHT, dc, map, act)
+        }
+        (Some(']'), Coord(0, _)) => {
+            do_move(c + dc, dc, map, act) && do_move(c + dc + LEFT, dc, map, act)
+        }
         _ => unreachable!(),
     };
 
     if can_move && act {
-        twod!(map[dc(c)]) = twod!(map[c]);
-        twod!(map[c]) = '.';
+        let moved = map.get_mut(&c).map(|cell| std::mem::replace(cell, '.'));
+        if let (Some(moved), Some(target)) = (moved, map.get_mut(&(c + dc))) {
+            *target = moved;
+        }
     }
     can_move
 }
 
-fn solve(mut map: Vec<Vec<char>>, moves: &str, acting_level: &[bool]) -> usize {
-    let mut robot = (0..map[0].len())
-        .cartesian_product(0..map.len())
-        .find(|(x, y)| map[*y][*x] == '@')
-        .unwrap();
+fn solve(mut map: Warehouse, mut robot: Coord, moves: &str, acting_level: &[bool]) -> usize {
     moves.chars().for_each(|dir| {
         if acting_level
             .iter()
             .all(|&act| do_move(robot, as_move(dir), &mut map, act))
         {
-            robot = as_move(dir)(robot);
+            robot += as_move(dir);
         }
     });
-    (0..map.len())
-        .cartesian_product(0..map[0].len())
-        .filter_map(|(y, x)| (map[y][x] == 'O' || map[y][x] == '[').then_some(y * 100 + x))
+    map.positions(|c| *c == 'O' || *c == '[')
+        .map(|Coord(x, y)| (y * 100 + x) as usize)
         .sum()
 }
 
 #[aoc_generator(day15)]
-fn parse_day15(input: &str) -> Option<(Vec<Vec<char>>, String)> {
-    let (map, moves) = input.split_once("\n\n")?;
-    let map = map.split("\n").map(|line| line.chars().collect()).collect();
-    Some((map, moves.replace("\n", "")))
+fn parse_day15(input: &str) -> Result<ParsedInput, Report> {
+    let (map, moves) = input
+        .split_once("\n\n")
+        .ok_or(eyre!("Missing moves after the warehouse"))?;
+    let warehouse = CharGrid::new()
+        .cell('.', '.')
+        .cell('#', '#')
+        .cell('O', 'O')
+        .entity('@', "robot", '.')
+        .load(map)?;
+    let robot = warehouse.entity("robot");
+    Ok((warehouse.field, robot, moves.replace("\n", "")))
 }
 
 #[aoc(day15, part1)]
-fn solve_part1((map, moves): &(Vec<Vec<char>>, String)) -> usize {
-    solve(map.clone(), moves, &[true])
+fn solve_part1((map, robot, moves): &ParsedInput) -> Option<usize> {
+    Some(solve(map.clone(), (*robot)?, moves, &[true]))
 }
 
 #[aoc(day15, part2)]
-fn solve_part2((map, moves): &(Vec<Vec<char>>, String)) -> usize {
-    let double = map
-        .iter()
-        .map(|line| {
-            line.iter()
-                .flat_map(|&c| match c {
-                    '@' => vec![c, '.'],
-                    'O' => vec!['[', ']'],
-                    c => vec![c, c],
-                })
-                .collect()
-        })
-        .collect();
-
-    solve(double, moves, &[false, true])
+fn solve_part2((map, robot, moves): &ParsedInput) -> Option<usize> {
+    let wide = Scaled::new(map, 2, 1);
+    let double = TableField::from_fn(*wide.definition_area(), |c| match wide.get(&c) {
+        Some('O') if c.0 % 2 == 0 => '[',
+        Some('O') => ']',
+        Some(cell) => *cell,
+        None => '#',
+    });
+    let Coord(x, y) = (*robot)?;
+
+    Some(solve(double, Coord(2 * x, y), moves, &[false, true]))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day15::{parse_day15, solve_part1, solve_part2};
+    use indoc::indoc;
+
+    #[test]
+    fn it_pushes_boxes() {
+        let input = parse_day15(indoc! {"
+            ########
+            #..O.O.#
+            ##@.O..#
+            #...O..#
+            #.#.O..#
+            #...O..#
+            #......#
+            ########
+
+            <^^>>>vv<v>>v<<
+        "})
+        .unwrap();
+        assert_eq!(solve_part1(&input), Some(2028));
+    }
+
+    #[test]
+    fn it_pushes_wide_boxes() {
+        let input = parse_day15(indoc! {"
+            #######
+            #...#.#
+            #.....#
+            #..OO@#
+            #..O..#
+            #.....#
+            #######
+
+            <vv<<^^<<^^
+        "})
+        .unwrap();
+        assert_eq!(solve_part2(&input), Some(618));
+    }
 }
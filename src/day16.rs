@@ -1,94 +1,129 @@
 use aoc_runner_derive::{aoc, aoc_generator};
-use itertools::Itertools;
-use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use eyre::Report;
 
-#[aoc_generator(day16)]
-fn parse_day16(input: &str) -> Vec<Vec<char>> {
-    input
-        .split("\n")
-        .map(|line| line.chars().collect())
-        .collect()
-}
+use crate::pathfinding::{dijkstra, OptimalPaths};
+use crate::space2d::load::CharGrid;
+use crate::space2d::{moved, Coord, Direction, Field, TableField};
 
-type Coord = (usize, usize);
-type PredMap = HashMap<(Coord, usize), Vec<(Coord, usize)>>;
+// Tells whether there is a wall on each position of the maze
+type Walls = TableField<bool>;
+type ParsedInput = (Walls, Option<(Coord, Coord)>);
 
-static MOVE_TOWARD: [fn(Coord) -> Coord; 4] = [
-    |(x, y)| (x + 1, y),
-    |(x, y)| (x, y - 1),
-    |(x, y)| (x - 1, y),
-    |(x, y)| (x, y + 1),
-];
+// Reindeer position, and the direction it faces
+type State = (Coord, Direction);
 
-fn find_entity(map: &[Vec<char>], entity: char) -> Option<Coord> {
-    (0..map[0].len())
-        .cartesian_product(0..map.len())
-        .find(|(x, y)| map[*y][*x] == entity)
+#[aoc_generator(day16)]
+fn parse_day16(input: &str) -> Result<ParsedInput, Report> {
+    let maze = CharGrid::new()
+        .cell('.', false)
+        .cell('#', true)
+        .entity('S', "start", false)
+        .entity('E', "end", false)
+        .load(input)?;
+    let ends = maze.entity("start").zip(maze.entity("end"));
+    Ok((maze.field, ends))
 }
 
-fn move_or_rotate(curr_pos: Coord, curr_dir: usize, rot: usize) -> (Coord, usize) {
-    let next_dir = (curr_dir + rot) % 4;
-    let next_pos = if rot == 0 {
-        MOVE_TOWARD[next_dir](curr_pos)
-    } else {
-        curr_pos
-    };
-    (next_pos, next_dir)
+fn successors(walls: &Walls, (pos, dir): &State) -> Vec<(State, usize)> {
+    let (pos, dir) = (*pos, *dir);
+    [
+        ((moved(&pos, &dir), dir), 1),
+        ((pos, dir.rotate()), 1000),
+        ((pos, dir.rotate().rotate().rotate()), 1000),
+    ]
+    .into_iter()
+    .filter(|((pos, _), _)| walls.get(pos) == Some(&false))
+    .collect()
 }
 
-fn find_path(map: &[Vec<char>]) -> Option<(Coord, usize, isize, PredMap)> {
-    let (start, goal) = (find_entity(map, 'S')?, find_entity(map, 'E')?);
-    let (init_dir, init_score) = (0usize, 0isize);
-    let mut stack = BinaryHeap::from([(init_score, start, init_dir)]);
-    let mut path_node = HashMap::from([((start, init_dir), init_score)]);
-    let mut preds = PredMap::new();
-
-    while let Some((score, curr_pos, curr_dir)) = stack.pop() {
-        for (rot, cost) in [(3, -1000), (0, -1), (1, -1000)] {
-            let next_node @ (next_pos, next_dir) = move_or_rotate(curr_pos, curr_dir, rot);
-            let new_score = score + cost;
-            let old_score = path_node.get(&(next_pos, next_dir)).copied();
-
-            if map[next_pos.1][next_pos.0] != '#' && Some(new_score) >= old_score {
-                if Some(new_score) > old_score && curr_pos != goal {
-                    stack.push((new_score, next_pos, next_dir));
-                }
-
-                path_node.remove(&next_node);
-                path_node.insert(next_node, new_score);
-
-                // Update next node predecessors
-                let mut new_preds = vec![(curr_pos, curr_dir)];
-                let olds = preds.remove(&next_node).unwrap_or_default().into_iter();
-                let keep_old_preds = old_score == Some(new_score);
-                new_preds.extend(olds.take_while(|_| keep_old_preds));
-                preds.insert(next_node, new_preds);
-            }
-        }
-    }
-    let (direction_arrival, score) = (0..4)
-        .filter_map(|dir| Some((dir, path_node.get(&(goal, dir))?)))
-        .max_by_key(|(_dir, score)| **score)?;
-    Some((goal, direction_arrival, -*score, preds))
+fn find_paths((walls, ends): &ParsedInput) -> Option<OptimalPaths<State, usize>> {
+    let (start, end) = (*ends)?;
+    dijkstra(
+        [(start, Direction::Ri)],
+        |state| successors(walls, state),
+        |(pos, _)| *pos == end,
+    )
 }
 
 #[aoc(day16, part1)]
-fn solve_part1(map: &[Vec<char>]) -> Option<isize> {
-    Some(find_path(map)?.2)
+fn solve_part1(input: &ParsedInput) -> Option<usize> {
+    Some(find_paths(input)?.cost())
 }
 
 #[aoc(day16, part2)]
-fn solve_part2(map: &[Vec<char>]) -> Option<usize> {
-    let (goal, arrival_dir, _, preds) = find_path(map)?;
+fn solve_part2(input: &ParsedInput) -> Option<usize> {
+    let tiles = find_paths(input)?.projected_on_paths(|(pos, _)| *pos);
+    Some(tiles.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day16::{parse_day16, solve_part1, solve_part2};
+    use indoc::indoc;
+
+    fn first_example() -> &'static str {
+        indoc! {"
+            ###############
+            #.......#....E#
+            #.#.###.#.###.#
+            #.....#.#...#.#
+            #.###.#####.#.#
+            #.#.#.......#.#
+            #.#.#####.###.#
+            #...........#.#
+            ###.#.#####.#.#
+            #...#.....#.#.#
+            #.#.#.###.#.#.#
+            #.....#...#.#.#
+            #.###.#.#.#.#.#
+            #S..#.....#...#
+            ###############"
+        }
+    }
 
-    let mut visited = HashSet::new();
-    let mut stack_back = VecDeque::from([(goal, arrival_dir)]);
-    while let Some((pos, curr_dir)) = stack_back.pop_front() {
-        match (visited.insert((pos, curr_dir)), preds.get(&(pos, curr_dir))) {
-            (true, Some(preds)) => stack_back.extend(preds),
-            _ => (),
+    fn second_example() -> &'static str {
+        indoc! {"
+            #################
+            #...#...#...#..E#
+            #.#.#.#.#.#.#.#.#
+            #.#.#.#...#...#.#
+            #.#.#.#.###.#.#.#
+            #...#.#.#.....#.#
+            #.#.#.#.#.#####.#
+            #.#...#.#.#.....#
+            #.#.#####.#.###.#
+            #.#.#.......#...#
+            #.#.###.#####.###
+            #.#.#...#.....#.#
+            #.#.#.#####.###.#
+            #.#.#.........#.#
+            #.#.#.#########.#
+            #S#.............#
+            #################"
         }
     }
 
-    Some(visited.into_iter().counts_by(|(coord, _dir)| coord).len())
+    #[test]
+    fn it_finds_the_lowest_score() {
+        assert_eq!(
+            solve_part1(&parse_day16(first_example()).unwrap()),
+            Some(7036)
+        );
+        assert_eq!(
+            solve_part1(&parse_day16(second_example()).unwrap()),
+            Some(11048)
+        );
+    }
+
+    #[test]
+    fn it_counts_tiles_on_best_paths() {
+        assert_eq!(
+            solve_part2(&parse_day16(first_example()).unwrap()),
+            Some(45)
+        );
+        assert_eq!(
+            solve_part2(&parse_day16(second_example()).unwrap()),
+            Some(64)
+        );
+    }
 }
@@ -1,81 +1,1060 @@
-use crate::space2d::{Coord, Field};
-use crate::tree_reduce::{TreeElement, TreeReduce};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::ops::Add;
+use std::rc::Rc;
+
+use crate::space2d::render::{Overlay, Render};
+use crate::space2d::{Coord, Field, FieldMut, TableField};
+use crate::tree_reduce::TreeElement;
 
 pub mod neighbors {
     use crate::space2d::Coord;
 
     pub static FOUR: [Coord; 4] = [Coord(1, 0), Coord(0, 1), Coord(-1, 0), Coord(0, -1)];
+
+    pub static DIAGONALS: [Coord; 4] = [Coord(1, 1), Coord(-1, 1), Coord(-1, -1), Coord(1, -1)];
+
+    pub static EIGHT: [Coord; 8] = [
+        Coord(1, 0),
+        Coord(1, 1),
+        Coord(0, 1),
+        Coord(-1, 1),
+        Coord(-1, 0),
+        Coord(-1, -1),
+        Coord(0, -1),
+        Coord(1, -1),
+    ];
+
+    // Every delta `d` other than Coord(0, 0) with |d.0| + |d.1| <= radius
+    pub fn manhattan(radius: usize) -> Vec<Coord> {
+        let r = radius as isize;
+        (-r..=r)
+            .flat_map(|y| {
+                let rx = r - y.abs();
+                (-rx..=rx).map(move |x| Coord(x, y))
+            })
+            .filter(|d| *d != Coord(0, 0))
+            .collect()
+    }
+
+    // Every delta `d` other than Coord(0, 0) with max(|d.0|, |d.1|) <= radius
+    pub fn chebyshev(radius: usize) -> Vec<Coord> {
+        let r = radius as isize;
+        (-r..=r)
+            .flat_map(|y| (-r..=r).map(move |x| Coord(x, y)))
+            .filter(|d| *d != Coord(0, 0))
+            .collect()
+    }
 }
 
-// Describes how to explore the 2d space
-pub trait Explore {
+// Coordinates of a space explorations move through
+pub trait Position: Copy + Eq + Hash + Add<Output = Self> {
+    // Direction of no move at all
+    const STILL: Self;
+}
+
+impl Position for Coord {
+    const STILL: Coord = Coord(0, 0);
+}
+
+// Cells explorations move through, whatever the dimension of their coords
+//
+// Every `Field` is a 2d space.
+pub trait Space {
+    type Coord: Position;
+    type Out;
+
+    fn cell(&self, coord: &Self::Coord) -> Option<&Self::Out>;
+    // Coord of the definition area standing for the same cell, None if there is none
+    fn canonical(&self, coord: &Self::Coord) -> Option<Self::Coord>;
+    // Cells of the definition area holding a value, in coord order
+    fn cells(&self) -> impl Iterator<Item = (Self::Coord, &Self::Out)>;
+}
+
+impl<F: Field> Space for F {
+    type Coord = Coord;
+    type Out = F::Out;
+
+    fn cell(&self, coord: &Coord) -> Option<&F::Out> {
+        self.get(coord)
+    }
+
+    fn canonical(&self, coord: &Coord) -> Option<Coord> {
+        Field::canonical(self, coord)
+    }
+
+    fn cells(&self) -> impl Iterator<Item = (Coord, &F::Out)> {
+        self.iter()
+    }
+}
+
+// Describes how to explore the space, 2d unless told otherwise
+pub trait Explore<C = Coord> {
     type T;
 
-    fn considered_directions(&self) -> Vec<Coord>;
+    fn considered_directions(&self) -> Vec<C>;
     fn filtered_directions(&self, value: &Self::T, parent_value: &Self::T) -> bool;
     fn reached_target(&self, value: &Self::T) -> bool;
 }
 
+// Where an exploration stands, and how it got there
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step<T, S, C = Coord> {
+    pub coord: C,
+    pub value: T,
+    // Move from the previous cell, `Position::STILL` for the start
+    pub direction: C,
+    // Number of moves since the start
+    pub depth: usize,
+    // Cells before this one
+    pub path: Trail<C>,
+    // Explorer state, carried along the path
+    pub state: S,
+}
+
+// Cells followed since the start, shared by the steps branching from them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trail<C = Coord>(Option<Rc<TrailCell<C>>>);
+
+#[derive(Debug, PartialEq, Eq)]
+struct TrailCell<C> {
+    coord: C,
+    // Move onto the cell, `Position::STILL` for the start
+    direction: C,
+    before: Trail<C>,
+}
+
+impl<C> Default for Trail<C> {
+    fn default() -> Self {
+        Trail(None)
+    }
+}
+
+impl<C: Copy> Trail<C> {
+    // Same trail, going on to `coord` by moving toward `direction`
+    //
+    // Directions are kept as they are, since coords of a wrapping field may jump across it.
+    pub fn then(&self, coord: C, direction: C) -> Trail<C> {
+        Trail(Some(Rc::new(TrailCell {
+            coord,
+            direction,
+            before: self.clone(),
+        })))
+    }
+
+    pub fn last(&self) -> Option<C> {
+        self.0.as_ref().map(|cell| cell.coord)
+    }
+
+    fn cells_back(&self) -> impl Iterator<Item = &TrailCell<C>> {
+        std::iter::successors(self.0.as_deref(), |cell| cell.before.0.as_deref())
+    }
+
+    // Cells from the last one back to the start
+    pub fn iter_back(&self) -> impl Iterator<Item = C> + '_ {
+        self.cells_back().map(|cell| cell.coord)
+    }
+
+    // Moves from each cell to the next one, from the start
+    pub fn directions(&self) -> Vec<C> {
+        let mut directions = self
+            .cells_back()
+            .filter(|cell| cell.before.0.is_some())
+            .map(|cell| cell.direction)
+            .collect::<Vec<_>>();
+        directions.reverse();
+        directions
+    }
+
+    // Cells from the start
+    pub fn to_vec(&self) -> Vec<C> {
+        let mut coords = self.iter_back().collect::<Vec<_>>();
+        coords.reverse();
+        coords
+    }
+}
+
+// Describes how to explore the space, knowing the whole path followed so far
+//
+// Every `Explore` is also an `ExploreSteps` without state.
+pub trait ExploreSteps<C = Coord> {
+    type T;
+    type State: Clone;
+
+    fn initial_state(&self) -> Self::State;
+    fn directions_from(&self, step: &Step<Self::T, Self::State, C>) -> Vec<C>;
+    // State after moving toward `direction` onto a cell holding `value`, None if not allowed
+    fn step_to(
+        &self,
+        from: &Step<Self::T, Self::State, C>,
+        direction: C,
+        value: &Self::T,
+    ) -> Option<Self::State>;
+    fn is_target(&self, step: &Step<Self::T, Self::State, C>) -> bool;
+}
+
+impl<C, E: Explore<C>> ExploreSteps<C> for E {
+    type T = E::T;
+    type State = ();
+
+    fn initial_state(&self) {}
+
+    fn directions_from(&self, _step: &Step<Self::T, (), C>) -> Vec<C> {
+        self.considered_directions()
+    }
+
+    fn step_to(&self, from: &Step<Self::T, (), C>, _direction: C, value: &Self::T) -> Option<()> {
+        self.filtered_directions(value, &from.value).then_some(())
+    }
+
+    fn is_target(&self, step: &Step<Self::T, (), C>) -> bool {
+        self.reached_target(&step.value)
+    }
+}
 
 // What is gathered at the end of the path, and how it is aggregated/simplified
 // at each cross road when backtracking
-pub trait Gather<T> {
+pub trait Gather<T, C = Coord> {
     type Loot; // L for loot
 
-    fn bring_back(&self, coord: &Coord, value: &T, reached_target: bool) -> Self::Loot;
+    fn bring_back(&self, coord: &C, value: &T, reached_target: bool) -> Self::Loot;
     fn gather(&self, it: impl Iterator<Item = Self::Loot>) -> Self::Loot;
+
+    // Same as `bring_back`, for gatherers needing to know how the end of the path was reached
+    fn bring_back_step<S>(&self, step: &Step<T, S, C>, reached_target: bool) -> Self::Loot {
+        self.bring_back(&step.coord, &step.value, reached_target)
+    }
+}
+
+// Gatherers whose loot only depends on the node it is brought back from, not on the path leading
+// to it as it may when using `bring_back_step`, so that it can be memoised
+pub trait NodeGather<T, C = Coord>: Gather<T, C> {}
+
+// Way followed from the start to a target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route<C = Coord> {
+    // Cells from the start to the target, both included
+    pub coords: Vec<C>,
+    // Move from each cell to the next one
+    pub directions: Vec<C>,
 }
 
+impl<C> Route<C> {
+    pub fn target(&self) -> Option<&C> {
+        self.coords.last()
+    }
+}
+
+impl Route {
+    // Arrows along the route, leaving the target visible
+    pub fn overlay(&self) -> Overlay {
+        Overlay::Path(self.coords.clone())
+    }
+}
+
+// Gathers the routes leading to targets, keeping only the first `max_paths` ones
+pub struct RecordRoutes {
+    max_paths: usize,
+}
+
+impl RecordRoutes {
+    pub fn new(max_paths: usize) -> Self {
+        RecordRoutes { max_paths }
+    }
+}
+
+impl<T, C: Position> Gather<T, C> for RecordRoutes {
+    type Loot = Vec<Route<C>>;
+
+    // Never called by explorations, routes are brought back along with their path
+    fn bring_back(&self, _coord: &C, _value: &T, _reached_target: bool) -> Self::Loot {
+        vec![]
+    }
+
+    fn gather(&self, it: impl Iterator<Item = Self::Loot>) -> Self::Loot {
+        it.flatten().take(self.max_paths).collect()
+    }
+
+    fn bring_back_step<S>(&self, step: &Step<T, S, C>, reached_target: bool) -> Self::Loot {
+        if !reached_target || self.max_paths == 0 {
+            return vec![];
+        }
+        let trail = step.path.then(step.coord, step.direction);
+        vec![Route {
+            coords: trail.to_vec(),
+            directions: trail.directions(),
+        }]
+    }
+}
+
+// Draw the routes over the field, later ones hiding earlier ones where they cross
+pub fn render_routes<F: Field>(
+    field: &F,
+    routes: &[Route],
+    cell: impl Fn(&Coord, &F::Out) -> char,
+) -> String {
+    routes
+        .iter()
+        .fold(Render::new(field, cell), |render, route| {
+            render.overlay(route.overlay())
+        })
+        .render()
+}
+
+// Loot of each node already explored
+type Memo<C, T, L> = RefCell<HashMap<(C, T), L>>;
+
+// Cells an exploration won't go back to
+//
+// Exploration only goes through cells of the field definition area, a wrapping field mapping each
+// coord into it with `Field::canonical`. When tracked, cells are gone through at most once per path,
+// so exploration terminates on any field. It may still take a time exponential in the area size
+// when tracked per path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Visited {
+    // Cells may be explored again, so exploration only ends if the explorer forbids cycles
+    #[default]
+    Untracked,
+    // A path doesn't go through the same cell twice, but other paths may go through it
+    PerPath,
+    // Each cell is explored from the first path reaching it only
+    Global,
+}
+
+// Loot brought back from each source of an exploration, and all of it gathered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourcesLoot<L, C = Coord> {
+    pub per_source: Vec<(C, L)>,
+    pub total: L,
+}
+
+static MEMO_CUT_SHORT: &str = "Memoised explorations can't cut paths short by tracking visited \
+    cells or stopping on the first target";
+
 // Combine exploration and gathering to compute result
-pub struct Exploration<'a, F: Field, E: Explore, G: Gather<F::Out>> {
+pub struct Exploration<'a, F, E, G>
+where
+    F: Space,
+    E: ExploreSteps<F::Coord>,
+    G: Gather<F::Out, F::Coord>,
+{
     field: &'a F,
     explore2d: E,
     gather: G,
-    //stop_on_target_reached: bool,
+    memo: Option<Memo<F::Coord, F::Out, G::Loot>>,
+    share_memo: bool,
+    visited: Visited,
+    visited_cells: RefCell<HashSet<F::Coord>>,
+    stop_on_target_reached: bool,
+    first_target_only: bool,
+    target_found: Cell<bool>,
 }
 
-impl<'a, F: Field, E: Explore, G: Gather<F::Out>> Exploration<'a, F, E, G> {
+impl<'a, F, E, G> Exploration<'a, F, E, G>
+where
+    F: Space,
+    E: ExploreSteps<F::Coord>,
+    G: Gather<F::Out, F::Coord>,
+{
     pub fn new(field: &'a F, explore2d: E, gather: G) -> Self {
         Self {
             field,
             explore2d,
             gather,
+            memo: None,
+            share_memo: true,
+            visited: Visited::Untracked,
+            visited_cells: RefCell::new(HashSet::new()),
+            stop_on_target_reached: true,
+            first_target_only: false,
+            target_found: Cell::new(false),
+        }
+    }
+
+    // Whether paths end on targets, which they do by default, or go on past them
+    pub fn stop_on_target_reached(mut self, stop: bool) -> Self {
+        self.stop_on_target_reached = stop;
+        self
+    }
+
+    // Stop the whole exploration once a target is reached: nodes still pending then bring back
+    // the loot of an empty gathering
+    pub fn first_target_only(mut self) -> Self {
+        assert!(self.memo.is_none(), "{MEMO_CUT_SHORT}");
+        self.first_target_only = true;
+        self
+    }
+
+    // Visited cells are tracked by `explore`, each call starting from scratch
+    pub fn tracking(mut self, visited: Visited) -> Self {
+        let cut_short = visited != Visited::Untracked;
+        assert!(self.memo.is_none() || !cut_short, "{MEMO_CUT_SHORT}");
+        self.visited = visited;
+        self
+    }
+
+    // Whether `from_all` keeps the memo from one source to the next, which it does by default
+    pub fn share_memo(mut self, share: bool) -> Self {
+        self.share_memo = share;
+        self
+    }
+
+    // First step of an exploration from the node
+    pub fn start(&self, (coord, value): &(F::Coord, F::Out)) -> Step<F::Out, E::State, F::Coord>
+    where
+        F::Out: Copy,
+    {
+        Step {
+            coord: *coord,
+            value: *value,
+            direction: <F::Coord as Position>::STILL,
+            depth: 0,
+            path: Trail::default(),
+            state: self.explore2d.initial_state(),
         }
     }
 }
 
-impl<'a, F, E, G> TreeReduce<(Coord, F::Out), G::Loot> for Exploration<'a, F, E, G>
+impl<F, E, G> Exploration<'_, F, E, G>
 where
-    E: Explore<T = F::Out>,
-    F::Out: Copy,
-    F: Field,
-    G: Gather<F::Out>,
+    F: Space,
+    E: ExploreSteps<F::Coord, State = ()>,
+    G: NodeGather<F::Out, F::Coord>,
+{
+    // Explore each node only once, reusing its loot when reached again by another path
+    //
+    // Loot must then only depend on the node, not on the path leading to it: explorers can't have
+    // a state, and memoised explorations can't track visited cells or stop on the first target,
+    // as they cut some paths short
+    pub fn memoised(mut self) -> Self {
+        let cut_short = self.first_target_only || self.visited != Visited::Untracked;
+        assert!(!cut_short, "{MEMO_CUT_SHORT}");
+        self.memo = Some(RefCell::new(HashMap::new()));
+        self
+    }
+}
+
+impl<F, E, G> Exploration<'_, F, E, G>
+where
+    E: ExploreSteps<F::Coord, T = F::Out>,
+    F::Out: Copy + Eq + Hash,
+    F: Space,
+    G: Gather<F::Out, F::Coord>,
+    G::Loot: Clone,
 {
-    fn generate_child(
+    // Loot brought back from the node, using the memo when there is one
+    // The memo is kept between calls, so nodes shared by several starts are explored once
+    pub fn explore(&self, node: &(F::Coord, F::Out)) -> G::Loot {
+        self.target_found.set(false);
+        *self.visited_cells.borrow_mut() = match self.visited {
+            Visited::Global => HashSet::from([node.0]),
+            Visited::Untracked | Visited::PerPath => HashSet::new(),
+        };
+        self.explore_step(&self.start(node))
+    }
+
+    // Forget loots memoised so far
+    pub fn clear_memo(&self) {
+        if let Some(memo) = &self.memo {
+            memo.borrow_mut().clear();
+        }
+    }
+
+    // Explore from every cell whose value fulfills `pred`, in coord order
+    pub fn from_all(
         &self,
-        _depth: usize,
-        (node, value): &(Coord, F::Out),
-    ) -> Vec<TreeElement<(Coord, F::Out), G::Loot>> {
-        self.explore2d
-            .considered_directions()
-            .iter()
-            .map(|c| *c + *node)
+        mut pred: impl FnMut(&F::Out) -> bool,
+    ) -> SourcesLoot<G::Loot, F::Coord> {
+        let per_source = self
+            .field
+            .cells()
+            .filter(|(_, value)| pred(value))
+            .map(|(coord, value)| {
+                if !self.share_memo {
+                    self.clear_memo();
+                }
+                (coord, self.explore(&(coord, *value)))
+            })
+            .collect::<Vec<_>>();
+        let total = self
+            .gather
+            .gather(per_source.iter().map(|(_, loot)| loot.clone()));
+        SourcesLoot { per_source, total }
+    }
+
+    // Depth first, as `TreeReduceCompute::compute`, also keeping the memo and the cells on the
+    // path up to date
+    fn explore_step(&self, step: &Step<F::Out, E::State, F::Coord>) -> G::Loot {
+        let node = (step.coord, step.value);
+        if let Some(loot) = self
+            .memo
+            .as_ref()
+            .and_then(|memo| memo.borrow().get(&node).cloned())
+        {
+            return loot;
+        }
+
+        let per_path = self.visited == Visited::PerPath;
+        if per_path {
+            self.visited_cells.borrow_mut().insert(step.coord);
+        }
+        let children = self.children(step);
+        let loot = if children.is_empty() {
+            self.collapse(step)
+        } else {
+            self.gather
+                .gather(children.into_iter().map(|child| match child {
+                    TreeElement::Node(child) => self.explore_step(&child),
+                    TreeElement::Collapsed(loot) => loot,
+                }))
+        };
+        if per_path {
+            self.visited_cells.borrow_mut().remove(&step.coord);
+        }
+
+        if let Some(memo) = &self.memo {
+            memo.borrow_mut().insert(node, loot.clone());
+        }
+        loot
+    }
+
+    // Steps going on from this one, and loots brought back without going further
+    fn children(
+        &self,
+        step: &Step<F::Out, E::State, F::Coord>,
+    ) -> Vec<TreeElement<Step<F::Out, E::State, F::Coord>, G::Loot>> {
+        if self.target_found.get() {
+            return vec![];
+        }
+        let reached_target = self.explore2d.is_target(step);
+        if reached_target && self.first_target_only {
+            self.target_found.set(true);
+            return vec![TreeElement::Collapsed(
+                self.gather.bring_back_step(step, true),
+            )];
+        }
+        if reached_target && self.stop_on_target_reached {
+            return vec![]; // Brought back by collapse
+        }
+
+        let children = self
+            .explore2d
+            .directions_from(step)
+            .into_iter()
             //
-            // Discard coords out of the field definition
-            .filter_map(|c| Some((c, *self.field.get(&c)?)))
+            // Discard coords out of the field definition, the others going by their canonical coord
+            .filter_map(|direction| {
+                let coord = self.field.canonical(&(step.coord + direction))?;
+                Some((direction, coord, *self.field.cell(&coord)?))
+            })
+            .filter(|(_, c, _)| match self.visited {
+                Visited::Untracked | Visited::Global => true,
+                Visited::PerPath => !self.visited_cells.borrow().contains(c),
+            })
             //
             // Now the explorer filter the potential directions
-            .filter(|(_, child_value)| self.explore2d.filtered_directions(child_value, value))
-            .map(|c| TreeElement::Node::<(Coord, F::Out), G::Loot>(c))
+            .filter_map(|(direction, coord, value)| {
+                let state = self.explore2d.step_to(step, direction, &value)?;
+                Some(Step {
+                    coord,
+                    value,
+                    direction,
+                    depth: step.depth + 1,
+                    path: step.path.then(step.coord, step.direction),
+                    state,
+                })
+            })
+            .filter(|child| {
+                self.visited != Visited::Global
+                    || self.visited_cells.borrow_mut().insert(child.coord)
+            })
+            .map(TreeElement::Node);
+
+        // Going on past a target, its loot is gathered along with the ones of its children
+        let target_loot = reached_target.then(|| self.gather.bring_back_step(step, true));
+        target_loot
+            .map(TreeElement::Collapsed)
+            .into_iter()
+            .chain(children)
             .collect()
     }
 
-    fn collapse(&self, node: &(Coord, F::Out)) -> G::Loot {
+    fn collapse(&self, step: &Step<F::Out, E::State, F::Coord>) -> G::Loot {
+        if self.target_found.get() {
+            return self.gather.gather(std::iter::empty());
+        }
         self.gather
-            .bring_back(&node.0, &node.1, self.explore2d.reached_target(&node.1))
+            .bring_back_step(step, self.explore2d.is_target(step))
+    }
+}
+
+// Result of a breadth first exploration
+pub struct DistanceMap {
+    // Number of moves from the nearest source, None for cells not reached
+    pub distances: TableField<Option<usize>>,
+    // Cell each one was reached from, None for sources and cells not reached
+    pub parents: TableField<Option<Coord>>,
+    // Target on which the exploration stopped, if asked to
+    pub target: Option<Coord>,
+}
+
+impl DistanceMap {
+    pub fn distance(&self, coord: &Coord) -> Option<usize> {
+        self.distances.get(coord).copied().flatten()
+    }
+
+    // Shortest route from a source to the cell, both included
+    pub fn path_to(&self, coord: &Coord) -> Option<Vec<Coord>> {
+        self.distance(coord)?;
+        let mut path = vec![*coord];
+        while let Some(Some(parent)) = self.parents.get(path.last()?) {
+            path.push(*parent);
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+/// Distances from the sources, moving as the explorer allows
+///
+/// * `sources` Cells at distance 0, ignored when out of the field
+/// * `stop_on_target` End the exploration on the first target reached. Cells at the same
+///   distance as this target may not be reached yet
+pub fn bfs<F, E>(
+    field: &F,
+    explore2d: &E,
+    sources: impl IntoIterator<Item = Coord>,
+    stop_on_target: bool,
+) -> DistanceMap
+where
+    F: Field,
+    E: Explore<T = F::Out>,
+{
+    let area = *field.definition_area();
+    let mut map = DistanceMap {
+        distances: TableField::filled(area, None),
+        parents: TableField::filled(area, None),
+        target: None,
+    };
+    let mut queue = VecDeque::new();
+    for source in sources {
+        if let (Some(distance @ None), Some(_)) =
+            (map.distances.get_mut(&source), field.get(&source))
+        {
+            *distance = Some(0);
+            queue.push_back(source);
+        }
+    }
+
+    let directions = explore2d.considered_directions();
+    while let Some(coord) = queue.pop_front() {
+        let Some(value) = field.get(&coord) else {
+            continue;
+        };
+        if stop_on_target && explore2d.reached_target(value) {
+            map.target = Some(coord);
+            break;
+        }
+        let distance = map.distance(&coord).unwrap_or_default();
+        for next in directions.iter().map(|d| *d + coord) {
+            let allowed = field
+                .get(&next)
+                .is_some_and(|next_value| explore2d.filtered_directions(next_value, value));
+            match map.distances.get_mut(&next) {
+                Some(next_distance @ None) if allowed => {
+                    *next_distance = Some(distance + 1);
+                    if let Some(parent) = map.parents.get_mut(&next) {
+                        *parent = Some(coord);
+                    }
+                    queue.push_back(next);
+                }
+                _ => (),
+            }
+        }
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bfs, neighbors, render_routes, Exploration, Explore, ExploreSteps, Gather, NodeGather,
+        RecordRoutes, Step, Visited,
+    };
+    use crate::space2d::{Coord, Field, TableField, WrappingField};
+    use crate::space3d::{self, BoundingBox3, Coord3, TableField3};
+
+    fn grid(rows: &[&str]) -> TableField<char> {
+        TableField::from_rows(rows.iter().map(|row| row.chars().collect()).collect()).unwrap()
+    }
+
+    // Moves in the four directions through anything but walls, looking for the exit
+    struct Walker;
+
+    impl Explore for Walker {
+        type T = char;
+
+        fn considered_directions(&self) -> Vec<Coord> {
+            neighbors::FOUR.into()
+        }
+
+        fn filtered_directions(&self, value: &char, _parent_value: &char) -> bool {
+            *value != '#'
+        }
+
+        fn reached_target(&self, value: &char) -> bool {
+            *value == 'E'
+        }
+    }
+
+    // Never climbs, so it may go back and forth between cells of the same height
+    struct Downhill;
+
+    impl Explore for Downhill {
+        type T = usize;
+
+        fn considered_directions(&self) -> Vec<Coord> {
+            neighbors::FOUR.into()
+        }
+
+        fn filtered_directions(&self, value: &usize, parent_value: &usize) -> bool {
+            *value <= *parent_value
+        }
+
+        fn reached_target(&self, value: &usize) -> bool {
+            *value == 0
+        }
+    }
+
+    // Climbs one step at a time, up to the top at 2
+    struct Climber;
+
+    impl Explore for Climber {
+        type T = usize;
+
+        fn considered_directions(&self) -> Vec<Coord> {
+            neighbors::FOUR.into()
+        }
+
+        fn filtered_directions(&self, value: &usize, parent_value: &usize) -> bool {
+            *value == parent_value + 1
+        }
+
+        fn reached_target(&self, value: &usize) -> bool {
+            *value == 2
+        }
+    }
+
+    // Climbs one unit at a time through a 3d field, up to the top at 6
+    struct Climber3;
+
+    impl Explore<Coord3> for Climber3 {
+        type T = usize;
+
+        fn considered_directions(&self) -> Vec<Coord3> {
+            space3d::neighbors::SIX.into()
+        }
+
+        fn filtered_directions(&self, value: &usize, parent_value: &usize) -> bool {
+            *value == parent_value + 1
+        }
+
+        fn reached_target(&self, value: &usize) -> bool {
+            *value == 6
+        }
+    }
+
+    // Heads for the goal, going at most `max_straight` times in a row in the same direction
+    struct Crucible {
+        goal: Coord,
+        max_straight: usize,
+    }
+
+    impl ExploreSteps for Crucible {
+        type T = char;
+        // Moves in a row in the current direction
+        type State = usize;
+
+        fn initial_state(&self) -> usize {
+            0
+        }
+
+        fn directions_from(&self, _step: &Step<char, usize>) -> Vec<Coord> {
+            neighbors::FOUR.into()
+        }
+
+        fn step_to(
+            &self,
+            from: &Step<char, usize>,
+            direction: Coord,
+            _value: &char,
+        ) -> Option<usize> {
+            let straight = match direction == from.direction {
+                true => from.state + 1,
+                false => 1,
+            };
+            (straight <= self.max_straight).then_some(straight)
+        }
+
+        fn is_target(&self, step: &Step<char, usize>) -> bool {
+            step.coord == self.goal
+        }
+    }
+
+    // Fewest moves to reach a target
+    struct FewestMoves;
+
+    impl<T> Gather<T> for FewestMoves {
+        type Loot = Option<usize>;
+
+        fn bring_back(&self, _coord: &Coord, _value: &T, _reached_target: bool) -> Option<usize> {
+            None
+        }
+
+        fn gather(&self, it: impl Iterator<Item = Option<usize>>) -> Option<usize> {
+            it.flatten().min()
+        }
+
+        fn bring_back_step<S>(&self, step: &Step<T, S>, reached_target: bool) -> Option<usize> {
+            reached_target.then_some(step.depth)
+        }
+    }
+
+    struct CountTargets;
+
+    impl<T, C> Gather<T, C> for CountTargets {
+        type Loot = usize;
+
+        fn bring_back(&self, _coord: &C, _value: &T, reached_target: bool) -> usize {
+            reached_target as usize
+        }
+
+        fn gather(&self, it: impl Iterator<Item = usize>) -> usize {
+            it.sum()
+        }
+    }
+
+    impl<T, C> NodeGather<T, C> for CountTargets {}
+
+    #[test]
+    fn it_lists_neighbours_within_a_radius() {
+        assert!(neighbors::manhattan(0).is_empty());
+        assert!(neighbors::chebyshev(0).is_empty());
+        assert_eq!(neighbors::manhattan(1).len(), 4);
+        assert_eq!(neighbors::chebyshev(1).len(), 8);
+        assert!(neighbors::FOUR
+            .iter()
+            .all(|d| neighbors::manhattan(1).contains(d)));
+        assert!(neighbors::EIGHT
+            .iter()
+            .all(|d| neighbors::chebyshev(1).contains(d)));
+        assert_eq!(neighbors::manhattan(2).len(), 12);
+        assert_eq!(neighbors::chebyshev(2).len(), 24);
+        assert!(neighbors::manhattan(2).contains(&Coord(-1, 1)));
+        assert!(!neighbors::manhattan(2).contains(&Coord(2, 1)));
+    }
+
+    #[test]
+    fn it_maps_distances_from_several_sources() {
+        let maze = grid(&["..#.", "#...", ".#E.", "##.#"]);
+        let sources = [Coord(0, 0), Coord(3, 0), Coord(9, 9)];
+        let map = bfs(&maze, &Walker, sources, false);
+        assert_eq!(map.target, None);
+        assert_eq!(map.distance(&Coord(3, 0)), Some(0));
+        assert_eq!(map.distance(&Coord(1, 1)), Some(2));
+        assert_eq!(map.distance(&Coord(2, 3)), Some(4));
+        assert_eq!(map.distance(&Coord(2, 0)), None);
+        assert_eq!(map.distance(&Coord(0, 2)), None);
+        assert_eq!(map.distance(&Coord(9, 9)), None);
+        assert_eq!(map.parents.get(&Coord(2, 1)), Some(&Some(Coord(3, 1))));
+        assert_eq!(map.parents.get(&Coord(0, 0)), Some(&None));
+        let path = [Coord(3, 0), Coord(3, 1), Coord(3, 2), Coord(2, 2)];
+        assert_eq!(map.path_to(&Coord(2, 2)), Some(path.to_vec()));
+        assert_eq!(map.path_to(&Coord(0, 2)), None);
+
+        let map = bfs(&maze, &Walker, sources, true);
+        assert_eq!(map.target, Some(Coord(2, 2)));
+        assert_eq!(map.path_to(&Coord(2, 2)), Some(path.to_vec()));
+        assert_eq!(map.distance(&Coord(2, 3)), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Memoised explorations can't cut paths short")]
+    fn it_refuses_to_track_cells_of_memoised_explorations() {
+        let maze = grid(&["...", ".E."]);
+        let _ = Exploration::new(&maze, Walker, CountTargets)
+            .memoised()
+            .tracking(Visited::Global);
+    }
+
+    #[test]
+    #[should_panic(expected = "Memoised explorations can't cut paths short")]
+    fn it_refuses_to_memoise_explorations_stopping_on_the_first_target() {
+        let maze = grid(&["...", ".E."]);
+        let _ = Exploration::new(&maze, Walker, CountTargets)
+            .first_target_only()
+            .memoised();
+    }
+
+    #[test]
+    fn it_terminates_when_tracking_visited_cells() {
+        let slope = TableField::from_rows(vec![vec![2, 2, 1], vec![2, 1, 0]]).unwrap();
+        let start = (Coord(0, 0), 2);
+
+        // Going down through (1, 0) then (2, 0) or (1, 1), or through (0, 1) then (1, 1)
+        let per_path = Exploration::new(&slope, Downhill, CountTargets).tracking(Visited::PerPath);
+        assert_eq!(per_path.explore(&start), 3);
+        let global = Exploration::new(&slope, Downhill, CountTargets).tracking(Visited::Global);
+        assert_eq!(global.explore(&start), 1);
+        // Each call starts from scratch
+        assert_eq!(global.explore(&start), 1);
+
+        // Only reaching the target across the seam, and back on the start going up or down
+        let ring = TableField::from_rows(vec![vec![1, 2, 2, 0]]).unwrap();
+        let wrapping = WrappingField::new(&ring);
+        let start = (Coord(0, 0), 1);
+        let per_path = Exploration::new(&wrapping, Downhill, CountTargets);
+        assert_eq!(per_path.tracking(Visited::PerPath).explore(&start), 1);
+        let global = Exploration::new(&wrapping, Downhill, CountTargets);
+        assert_eq!(global.tracking(Visited::Global).explore(&start), 1);
+        let plain = Exploration::new(&ring, Downhill, CountTargets);
+        assert_eq!(plain.tracking(Visited::PerPath).explore(&start), 0);
+
+        let routes = Exploration::new(&wrapping, Downhill, RecordRoutes::new(1))
+            .tracking(Visited::PerPath)
+            .explore(&start);
+        assert_eq!(routes[0].coords, vec![Coord(0, 0), Coord(3, 0)]);
+        assert_eq!(routes[0].directions, vec![Coord(-1, 0)]);
     }
 
-    fn reduce(&self, it: impl Iterator<Item = G::Loot>) -> G::Loot {
-        self.gather.gather(it)
+    #[test]
+    fn it_goes_on_past_targets_or_stops_on_the_first_one() {
+        // Three targets in a row
+        let slope = TableField::from_rows(vec![vec![1, 0, 0, 0]]).unwrap();
+        let start = (Coord(0, 0), 1);
+        let stopping = Exploration::new(&slope, Downhill, CountTargets);
+        assert_eq!(stopping.tracking(Visited::PerPath).explore(&start), 1);
+        let going_on = Exploration::new(&slope, Downhill, CountTargets)
+            .stop_on_target_reached(false)
+            .tracking(Visited::PerPath);
+        assert_eq!(going_on.explore(&start), 3);
+        let first_only = Exploration::new(&slope, Downhill, CountTargets)
+            .stop_on_target_reached(false)
+            .first_target_only()
+            .tracking(Visited::PerPath);
+        assert_eq!(first_only.explore(&start), 1);
+
+        // Three paths to the same target
+        let slope = TableField::from_rows(vec![vec![2, 2, 1], vec![2, 1, 0]]).unwrap();
+        let first_only = Exploration::new(&slope, Downhill, CountTargets)
+            .first_target_only()
+            .tracking(Visited::PerPath);
+        assert_eq!(first_only.explore(&(Coord(0, 0), 2)), 1);
+        assert_eq!(first_only.explore(&(Coord(1, 1), 1)), 1);
+    }
+
+    #[test]
+    fn it_explores_with_a_state() {
+        let field = grid(&[".....", "....."]);
+        let start = (Coord(0, 0), '.');
+        let crucible = |max_straight| Crucible {
+            goal: Coord(4, 1),
+            max_straight,
+        };
+        let paths = |max_straight| {
+            Exploration::new(&field, crucible(max_straight), CountTargets)
+                .tracking(Visited::PerPath)
+                .explore(&start)
+        };
+        let fewest_moves = |max_straight| {
+            Exploration::new(&field, crucible(max_straight), FewestMoves)
+                .tracking(Visited::PerPath)
+                .explore(&start)
+        };
+        assert_eq!(paths(5), 16);
+        assert_eq!(paths(3), 14);
+        assert_eq!(paths(1), 2);
+        assert_eq!(fewest_moves(3), Some(5));
+        // Zigzagging all the way
+        assert_eq!(fewest_moves(1), Some(7));
+    }
+
+    #[test]
+    fn it_explores_from_all_sources() {
+        let hill = TableField::from_rows(vec![vec![0, 1, 2], vec![1, 2, 0]]).unwrap();
+        let memo_size = |exploration: &Exploration<_, Climber, CountTargets>| {
+            exploration.memo.as_ref().map(|memo| memo.borrow().len())
+        };
+
+        let sharing = Exploration::new(&hill, Climber, CountTargets).memoised();
+        let loot = sharing.from_all(|height| *height == 0);
+        assert_eq!(loot.per_source, vec![(Coord(0, 0), 3), (Coord(2, 1), 0)]);
+        assert_eq!(loot.total, 3);
+        assert_eq!(memo_size(&sharing), Some(6));
+
+        // Only the cells explored from the last source are left
+        let not_sharing = Exploration::new(&hill, Climber, CountTargets)
+            .memoised()
+            .share_memo(false);
+        assert_eq!(not_sharing.from_all(|height| *height == 0), loot);
+        assert_eq!(memo_size(&not_sharing), Some(1));
+
+        let plain = Exploration::new(&hill, Climber, CountTargets);
+        assert_eq!(plain.from_all(|height| *height == 0), loot);
+        assert_eq!(plain.from_all(|height| *height == 1).total, 3);
+    }
+
+    #[test]
+    fn it_explores_3d_fields() {
+        let cube = BoundingBox3 {
+            xmin: 0,
+            xmax: 3,
+            ymin: 0,
+            ymax: 3,
+            zmin: 0,
+            zmax: 3,
+        };
+        let field = TableField3::from_fn(cube, |c| c.manhattan(&Coord3(0, 0, 0)));
+        let exploration = Exploration::new(&field, Climber3, CountTargets);
+        // Ways to order 2 moves along each axis: 6! / (2! 2! 2!)
+        assert_eq!(exploration.explore(&(Coord3(0, 0, 0), 0)), 90);
+        assert_eq!(exploration.explore(&(Coord3(2, 2, 1), 5)), 1);
+
+        let memoised = Exploration::new(&field, Climber3, CountTargets).memoised();
+        let loot = memoised.from_all(|value| *value == 5);
+        assert_eq!(loot.per_source[0], (Coord3(2, 2, 1), 1));
+        assert_eq!(loot.total, 3);
+    }
+
+    #[test]
+    fn it_records_routes_to_targets() {
+        let hill = TableField::from_rows(vec![vec![0, 1, 2], vec![1, 2, 0]]).unwrap();
+        let start = (Coord(0, 0), 0);
+        let routes = |max_paths| {
+            Exploration::new(&hill, Climber, RecordRoutes::new(max_paths)).explore(&start)
+        };
+
+        let all = routes(10);
+        assert_eq!(all.len(), 3);
+        let first = &all[0];
+        assert_eq!(first.coords, vec![Coord(0, 0), Coord(1, 0), Coord(2, 0)]);
+        assert_eq!(first.directions, vec![Coord(1, 0), Coord(1, 0)]);
+        assert_eq!(first.target(), Some(&Coord(2, 0)));
+        let last = &all[2];
+        assert_eq!(last.coords, vec![Coord(0, 0), Coord(0, 1), Coord(1, 1)]);
+        assert_eq!(last.directions, vec![Coord(0, 1), Coord(1, 0)]);
+
+        assert_eq!(routes(2), all[..2]);
+        assert!(routes(0).is_empty());
+        // The start alone when it is a target
+        let top = Exploration::new(&hill, Climber, RecordRoutes::new(1)).explore(&(Coord(2, 0), 2));
+        assert_eq!(top[0].coords, vec![Coord(2, 0)]);
+        assert!(top[0].directions.is_empty());
+
+        let height = |_: &Coord, height: &usize| char::from(b'0' + *height as u8);
+        assert_eq!(render_routes(&hill, &all[..1], height), ">>2\n120\n");
+        assert_eq!(render_routes(&hill, &all[2..], height), "v12\n>20\n");
+        assert_eq!(render_routes(&hill, &[], height), "012\n120\n");
     }
 }
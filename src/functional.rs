@@ -0,0 +1,185 @@
+/// Apply `step` until the value `key` stays the same between two iterations, and return the
+/// stable value
+///
+/// * `step` Lambda transforming the current value into the next one
+/// * `key` Lambda extracting what is compared between two consecutive values
+/// * `max_iterations` Give up after this many applications of `step`, None to never give up
+///
+/// Returns `Err` with the last computed value if no stable value was reached
+pub fn do_until_stable<T, K: PartialEq>(
+    init: T,
+    mut step: impl FnMut(T) -> T,
+    mut key: impl FnMut(&T) -> K,
+    max_iterations: Option<usize>,
+) -> Result<T, T> {
+    let mut value = init;
+    let mut previous_key = key(&value);
+    for _ in 0..max_iterations.unwrap_or(usize::MAX) {
+        value = step(value);
+        let new_key = key(&value);
+        if new_key == previous_key {
+            return Ok(value);
+        }
+        previous_key = new_key;
+    }
+    Err(value)
+}
+
+/// Apply `step` until `stop` holds for the current value, and return it along with the number of
+/// applications of `step`
+pub fn iterate_until<T>(
+    init: T,
+    mut step: impl FnMut(T) -> T,
+    mut stop: impl FnMut(&T) -> bool,
+) -> (usize, T) {
+    let mut value = init;
+    let mut iterations = 0;
+    while !stop(&value) {
+        value = step(value);
+        iterations += 1;
+    }
+    (iterations, value)
+}
+
+/// Cycle found in the sequence `init, f(init), f(f(init)), ...`
+///
+/// * `start` Index of the first value belonging to the cycle
+/// * `length` Number of values in the cycle, a fixed point being a cycle of length 1
+/// * `entry` The value at index `start`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle<T> {
+    pub start: usize,
+    pub length: usize,
+    pub entry: T,
+}
+
+impl<T> Cycle<T> {
+    pub fn is_fixed_point(&self) -> bool {
+        self.length == 1
+    }
+
+    /// Index of the value equal to the one at `index`, within the first occurrence of the cycle
+    pub fn equivalent_index(&self, index: usize) -> usize {
+        if index < self.start {
+            index
+        } else {
+            self.start + (index - self.start) % self.length
+        }
+    }
+}
+
+/// Find the cycle of the sequence generated by `f` from `init` with Floyd's tortoise and hare
+///
+/// The sequence must be eventually periodic, otherwise this never returns
+pub fn floyd<T: Clone + PartialEq>(init: T, f: impl Fn(&T) -> T) -> Cycle<T> {
+    // Hare goes twice as fast as tortoise, until they meet inside the cycle
+    let mut tortoise = f(&init);
+    let mut hare = f(&tortoise);
+    while tortoise != hare {
+        tortoise = f(&tortoise);
+        hare = f(&f(&hare));
+    }
+
+    // Distance between them is now a multiple of the cycle length, so walking both at the same
+    // pace from the start and from the meeting point makes them meet at the cycle entry
+    let mut start = 0;
+    tortoise = init;
+    while tortoise != hare {
+        tortoise = f(&tortoise);
+        hare = f(&hare);
+        start += 1;
+    }
+
+    let mut length = 1;
+    hare = f(&tortoise);
+    while tortoise != hare {
+        hare = f(&hare);
+        length += 1;
+    }
+
+    Cycle {
+        start,
+        length,
+        entry: tortoise,
+    }
+}
+
+/// Find the cycle of the sequence generated by `f` from `init` with Brent's algorithm
+///
+/// Needs fewer calls to `f` than `floyd`. The sequence must be eventually periodic, otherwise
+/// this never returns
+pub fn brent<T: Clone + PartialEq>(init: T, f: impl Fn(&T) -> T) -> Cycle<T> {
+    // Look for the cycle length, teleporting tortoise at each power of two
+    let mut power = 1;
+    let mut length = 1;
+    let mut tortoise = init.clone();
+    let mut hare = f(&init);
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare = f(&hare);
+        length += 1;
+    }
+
+    // Then move hare `length` steps ahead, and walk both until they meet at the cycle entry
+    tortoise = init.clone();
+    hare = init;
+    for _ in 0..length {
+        hare = f(&hare);
+    }
+    let mut start = 0;
+    while tortoise != hare {
+        tortoise = f(&tortoise);
+        hare = f(&hare);
+        start += 1;
+    }
+
+    Cycle {
+        start,
+        length,
+        entry: tortoise,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{brent, do_until_stable, floyd, iterate_until, Cycle};
+
+    // 0 -> 1 -> 2 -> 3 -> 4 -> 5 -> 3 -> ...
+    fn rho(x: &usize) -> usize {
+        if *x < 5 {
+            x + 1
+        } else {
+            3
+        }
+    }
+
+    #[test]
+    fn it_finds_cycle_start_and_length() {
+        let expected = Cycle {
+            start: 3,
+            length: 3,
+            entry: 3,
+        };
+        assert_eq!(floyd(0, rho), expected);
+        assert_eq!(brent(0, rho), expected);
+        assert_eq!(expected.equivalent_index(10), 4);
+    }
+
+    #[test]
+    fn it_finds_fixed_points() {
+        let halve = |x: &usize| x / 2;
+        assert!(floyd(100, halve).is_fixed_point());
+        assert_eq!(brent(100, halve).entry, 0);
+    }
+
+    #[test]
+    fn it_stops_when_stable() {
+        assert_eq!(do_until_stable(100, |x| x / 2, |x| *x, None), Ok(0));
+        assert_eq!(do_until_stable(100, |x| x / 2, |x| *x, Some(3)), Err(12));
+        assert_eq!(iterate_until(1, |x| x * 3, |x| *x > 100), (5, 243));
+    }
+}
@@ -5,19 +5,21 @@ mod relation;
 
 mod day04;
 
-mod coord_iter;
 mod day05;
 
 mod day06;
-mod space2d;
-mod functional;
+pub mod space2d;
+pub mod space3d;
+pub mod functional;
 
 mod day07;
 pub mod tree_reduce;
 mod day08;
 mod day09;
 mod day10;
-mod explore_2d;
+pub mod explore_2d;
+pub mod regions;
+pub mod pathfinding;
 mod day10_original;
 mod day11;
 mod day12;
@@ -0,0 +1,334 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::hash::Hash;
+use std::ops::Add;
+
+// Costs start at `Default::default()` and must never decrease along a path
+pub trait Cost: Copy + Ord + Add<Output = Self> + Default {}
+
+impl<C: Copy + Ord + Add<Output = C> + Default> Cost for C {}
+
+// Outcome of a search: every optimal way from the starts to the goals
+//
+// States are indexed in the order they were first met, predecessors of a state being the states
+// from which it is reached at its optimal cost. Moves costing nothing may give predecessors to
+// starts, or make cycles of predecessors: paths still end on their first start, and never go
+// through a state twice.
+pub struct OptimalPaths<S, C> {
+    states: Vec<S>,
+    index: HashMap<S, usize>,
+    starts: Vec<usize>,
+    costs: Vec<C>,
+    preds: Vec<Vec<usize>>,
+    goals: Vec<usize>,
+    cost: C,
+}
+
+/// Cheapest paths from any of the starts to any state fulfilling `goal`, None if there is none
+///
+/// * `successors` States reachable from a state, with the cost of each move
+pub fn dijkstra<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    successors: impl FnMut(&S) -> I,
+    goal: impl FnMut(&S) -> bool,
+) -> Option<OptimalPaths<S, C>>
+where
+    S: Clone + Eq + Hash,
+    C: Cost,
+    I: IntoIterator<Item = (S, C)>,
+{
+    astar(starts, successors, |_| C::default(), goal)
+}
+
+/// Same as `dijkstra`, exploring first the states looking closer to a goal
+///
+/// * `heuristic` Estimation of the cost from a state to the nearest goal. It must never
+///   overestimate it, nor decrease by more than the cost of a move, otherwise paths found may not
+///   be optimal
+pub fn astar<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut successors: impl FnMut(&S) -> I,
+    mut heuristic: impl FnMut(&S) -> C,
+    mut goal: impl FnMut(&S) -> bool,
+) -> Option<OptimalPaths<S, C>>
+where
+    S: Clone + Eq + Hash,
+    C: Cost,
+    I: IntoIterator<Item = (S, C)>,
+{
+    let mut paths = OptimalPaths {
+        states: vec![],
+        index: HashMap::new(),
+        starts: vec![],
+        costs: vec![],
+        preds: vec![],
+        goals: vec![],
+        cost: C::default(),
+    };
+    let mut heap = BinaryHeap::new();
+
+    for start in starts {
+        if !paths.index.contains_key(&start) {
+            let i = paths.push(start.clone(), C::default());
+            paths.starts.push(i);
+            heap.push(Reverse((heuristic(&start), C::default(), i)));
+        }
+    }
+
+    while let Some(Reverse((estimate, cost, i))) = heap.pop() {
+        if cost > paths.costs[i] {
+            continue; // Already reached by a cheaper path
+        }
+        if !paths.goals.is_empty() && estimate > paths.cost {
+            break;
+        }
+        let state = paths.states[i].clone();
+        if goal(&state) {
+            paths.cost = cost;
+            paths.goals.push(i);
+            continue;
+        }
+
+        for (next, step) in successors(&state) {
+            let next_cost = cost + step;
+            match paths.index.get(&next) {
+                Some(&j) if next_cost > paths.costs[j] => (),
+                Some(&j) if next_cost == paths.costs[j] => paths.preds[j].push(i),
+                known => {
+                    let j = match known {
+                        Some(&j) => {
+                            paths.costs[j] = next_cost;
+                            paths.preds[j] = vec![i];
+                            j
+                        }
+                        None => {
+                            let j = paths.push(next.clone(), next_cost);
+                            paths.preds[j].push(i);
+                            j
+                        }
+                    };
+                    heap.push(Reverse((next_cost + heuristic(&next), next_cost, j)));
+                }
+            }
+        }
+    }
+
+    (!paths.goals.is_empty()).then_some(paths)
+}
+
+impl<S: Clone + Eq + Hash, C: Cost> OptimalPaths<S, C> {
+    fn push(&mut self, state: S, cost: C) -> usize {
+        self.index.insert(state.clone(), self.states.len());
+        self.states.push(state);
+        self.costs.push(cost);
+        self.preds.push(vec![]);
+        self.states.len() - 1
+    }
+
+    pub fn cost(&self) -> C {
+        self.cost
+    }
+
+    // Goal states reached at the optimal cost
+    pub fn goals(&self) -> impl Iterator<Item = &S> {
+        self.goals.iter().map(|i| &self.states[*i])
+    }
+
+    // Starts end paths, even when moves costing nothing give them predecessors
+    fn ends_path(&self, i: usize) -> bool {
+        self.preds[i].is_empty() || self.starts.contains(&i)
+    }
+
+    // One of the optimal paths, from its start to its goal
+    //
+    // First predecessors of states other than starts never make cycles, being met before them.
+    pub fn path(&self) -> Vec<S> {
+        let mut path = vec![self.goals[0]];
+        while !self.ends_path(*path.last().unwrap()) {
+            path.push(self.preds[*path.last().unwrap()][0]);
+        }
+        path.iter().rev().map(|i| self.states[*i].clone()).collect()
+    }
+
+    // States from which this one is reached at its optimal cost
+    //
+    // Only meaningful for states on optimal paths: the search stops once goals are reached, so
+    // other states may not have been reached at their optimal cost.
+    pub fn predecessors(&self, state: &S) -> impl Iterator<Item = &S> {
+        let preds = self.index.get(state).map_or(&[][..], |i| &self.preds[*i]);
+        preds.iter().map(|i| &self.states[*i])
+    }
+
+    // Indices of the states on at least one optimal path
+    fn on_paths(&self) -> Vec<usize> {
+        let mut seen = vec![false; self.states.len()];
+        let mut stack = self.goals.clone();
+        let mut on_paths = vec![];
+        while let Some(i) = stack.pop() {
+            if !std::mem::replace(&mut seen[i], true) {
+                on_paths.push(i);
+                stack.extend(&self.preds[i]);
+            }
+        }
+        on_paths
+    }
+
+    // States on at least one optimal path
+    pub fn states_on_paths(&self) -> HashSet<&S> {
+        self.on_paths()
+            .into_iter()
+            .map(|i| &self.states[i])
+            .collect()
+    }
+
+    // Projections of the states on at least one optimal path, such as their positions
+    pub fn projected_on_paths<P: Eq + Hash>(&self, mut project: impl FnMut(&S) -> P) -> HashSet<P> {
+        self.on_paths()
+            .into_iter()
+            .map(|i| project(&self.states[i]))
+            .collect()
+    }
+
+    // Number of optimal paths, None if it overflows
+    // Moves costing nothing may create cycles, making it infinite: None as well, though `paths`
+    // then gives the paths not going through a state twice
+    pub fn count_paths(&self) -> Option<usize> {
+        let mut ways = vec![None; self.states.len()];
+        let mut visiting = vec![false; self.states.len()];
+        let mut stack = self
+            .goals
+            .iter()
+            .map(|goal| (*goal, false))
+            .collect::<Vec<_>>();
+        while let Some((i, preds_counted)) = stack.pop() {
+            if ways[i].is_some() {
+                continue;
+            }
+            let preds = &self.preds[i];
+            if self.ends_path(i) {
+                ways[i] = Some(1usize);
+            } else if preds_counted {
+                let count = preds
+                    .iter()
+                    .try_fold(0usize, |sum, p| sum.checked_add(ways[*p]?))?;
+                ways[i] = Some(count);
+            } else if std::mem::replace(&mut visiting[i], true) {
+                return None; // Back to a state whose predecessors are being counted: a cycle
+            } else {
+                stack.push((i, true));
+                stack.extend(preds.iter().map(|p| (*p, false)));
+            }
+        }
+        self.goals
+            .iter()
+            .try_fold(0usize, |sum, goal| sum.checked_add(ways[*goal]?))
+    }
+
+    // Every optimal path not going through a state twice, from its start to its goal, lazily
+    //
+    // Their number can grow exponentially with the size of the graph.
+    pub fn paths(&self) -> impl Iterator<Item = Vec<S>> + '_ {
+        let mut stack = self
+            .goals
+            .iter()
+            .map(|goal| vec![*goal])
+            .collect::<Vec<_>>();
+        std::iter::from_fn(move || {
+            while let Some(path) = stack.pop() {
+                let last = *path.last().unwrap();
+                if self.ends_path(last) {
+                    return Some(path.iter().rev().map(|i| self.states[*i].clone()).collect());
+                }
+                for pred in self.preds[last].iter().filter(|p| !path.contains(p)) {
+                    let mut longer = path.clone();
+                    longer.push(*pred);
+                    stack.push(longer);
+                }
+            }
+            None
+        })
+    }
+
+    pub fn all_paths(&self) -> Vec<Vec<S>> {
+        self.paths().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{astar, dijkstra};
+    use crate::explore_2d::neighbors;
+    use crate::space2d::Coord;
+
+    #[test]
+    fn it_finds_optimal_paths() {
+        // Moving on a 3x3 grid from the top left corner to the bottom right one
+        let successors = |c: &Coord| {
+            neighbors::FOUR
+                .iter()
+                .map(|d| *c + *d)
+                .filter(|n| (0..3).contains(&n.0) && (0..3).contains(&n.1))
+                .map(|n| (n, 1))
+                .collect::<Vec<_>>()
+        };
+        let paths = dijkstra([Coord(0, 0)], successors, |c| *c == Coord(2, 2)).unwrap();
+        assert_eq!(paths.cost(), 4usize);
+        assert_eq!(paths.path().len(), 5);
+        assert_eq!(paths.all_paths().len(), 6);
+        assert_eq!(paths.count_paths(), Some(6));
+        assert_eq!(paths.states_on_paths().len(), 9);
+        assert_eq!(paths.predecessors(&Coord(1, 1)).count(), 2);
+        assert_eq!(paths.projected_on_paths(|c| c.0 + c.1).len(), 5);
+
+        let heuristic = |c: &Coord| c.manhattan(&Coord(2, 2));
+        let guided = astar([Coord(0, 0)], successors, heuristic, |c| *c == Coord(2, 2)).unwrap();
+        assert_eq!(guided.cost(), 4);
+        assert_eq!(guided.all_paths().len(), 6);
+
+        assert!(dijkstra([Coord(0, 0)], successors, |c| *c == Coord(5, 5)).is_none());
+    }
+
+    #[test]
+    fn it_finds_a_path_through_moves_costing_nothing() {
+        let successors = |state: &char| match state {
+            'S' => vec![('A', 0)],
+            'A' => vec![('S', 0), ('G', 1)],
+            _ => vec![],
+        };
+        let paths = dijkstra(['S'], successors, |state| *state == 'G').unwrap();
+        assert_eq!(paths.cost(), 1);
+        assert_eq!(paths.path(), ['S', 'A', 'G']);
+    }
+
+    #[test]
+    fn it_lists_paths_around_cycles_costing_nothing() {
+        // A and B are reached from each other for free, each of them leading to a goal
+        let successors = |state: &char| match state {
+            'S' => vec![('A', 1), ('B', 1)],
+            'A' => vec![('B', 0), ('1', 1)],
+            'B' => vec![('A', 0), ('2', 1)],
+            _ => vec![],
+        };
+        let paths = dijkstra(['S'], successors, |state| state.is_ascii_digit()).unwrap();
+        assert_eq!(paths.cost(), 2);
+        assert_eq!(paths.goals().count(), 2);
+        assert_eq!(paths.count_paths(), None);
+        let mut all = paths
+            .all_paths()
+            .into_iter()
+            .map(String::from_iter)
+            .collect::<Vec<_>>();
+        all.sort();
+        assert_eq!(all, ["SA1", "SAB2", "SB2", "SBA1"]);
+
+        // Without cycle, moves costing nothing only add paths
+        let successors = |state: &char| match state {
+            'S' => vec![('A', 0), ('2', 1)],
+            'A' => vec![('1', 1)],
+            _ => vec![],
+        };
+        let paths = dijkstra(['S'], successors, |state| state.is_ascii_digit()).unwrap();
+        assert_eq!(paths.count_paths(), Some(2));
+        assert_eq!(paths.all_paths().len(), 2);
+    }
+}
@@ -0,0 +1,369 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::relation::Relation;
+use crate::space2d::{moved, BoundingBox, Coord, Direction, Field, FieldMut, TableField};
+
+// Connected components of a field
+//
+// Regions are labelled from 0, in reading order of their first cell.
+pub struct Regions {
+    // Label of the region of each cell, None for cells without value
+    labels: TableField<Option<usize>>,
+    cells: Vec<Vec<Coord>>,
+    adjacency: Vec<BTreeSet<usize>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegionStats {
+    pub area: usize,
+    pub bounding_box: BoundingBox,
+}
+
+/// Split the field into regions, without recursion so big fields can't overflow the stack
+///
+/// * `neighbourhood` Deltas from a cell to the cells it is connected to, such as `neighbors::FOUR`
+/// * `same_region` Tells whether two neighbour cells belong to the same region. It should be
+///   symmetric, otherwise the labelling depends on the order cells are visited
+pub fn components<F: Field>(
+    field: &F,
+    neighbourhood: &[Coord],
+    same_region: impl Relation<F::Out>,
+) -> Regions {
+    let area = *field.definition_area();
+    let mut labels = TableField::filled(area, None);
+    let mut cells = vec![];
+
+    for start in area.iter() {
+        if labels.get(&start) != Some(&None) || field.get(&start).is_none() {
+            continue;
+        }
+        let label = cells.len();
+        let mut region = vec![start];
+        let mut stack = vec![start];
+        if let Some(l) = labels.get_mut(&start) {
+            *l = Some(label);
+        }
+        while let Some(coord) = stack.pop() {
+            let Some(value) = field.get(&coord) else {
+                continue;
+            };
+            for delta in neighbourhood {
+                let neighbour = coord + *delta;
+                let connected = labels.get(&neighbour) == Some(&None)
+                    && field
+                        .get(&neighbour)
+                        .is_some_and(|other| same_region.relates((value, other)));
+                if let Some(l) = labels.get_mut(&neighbour).filter(|_| connected) {
+                    *l = Some(label);
+                    region.push(neighbour);
+                    stack.push(neighbour);
+                }
+            }
+        }
+        region.sort();
+        cells.push(region);
+    }
+
+    let mut adjacency = vec![BTreeSet::new(); cells.len()];
+    for coord in area.iter() {
+        if let Some(Some(label)) = labels.get(&coord) {
+            for delta in neighbourhood {
+                match labels.get(&(coord + *delta)) {
+                    Some(Some(other)) if other != label => {
+                        adjacency[*label].insert(*other);
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    Regions {
+        labels,
+        cells,
+        adjacency,
+    }
+}
+
+impl Regions {
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    // Field of region labels
+    pub fn labels(&self) -> &TableField<Option<usize>> {
+        &self.labels
+    }
+
+    pub fn label_of(&self, coord: &Coord) -> Option<usize> {
+        self.labels.get(coord).copied().flatten()
+    }
+
+    // Cells of the region, in reading order
+    pub fn cells(&self, label: usize) -> &[Coord] {
+        &self.cells[label]
+    }
+
+    // Cells of each region, indexed by label
+    pub fn all_cells(&self) -> impl Iterator<Item = &[Coord]> {
+        self.cells.iter().map(|cells| cells.as_slice())
+    }
+
+    // Labels of the regions touching this one through the neighbourhood
+    pub fn adjacent(&self, label: usize) -> &BTreeSet<usize> {
+        &self.adjacency[label]
+    }
+
+    pub fn stats(&self, label: usize) -> RegionStats {
+        let cells = &self.cells[label];
+        RegionStats {
+            area: cells.len(),
+            bounding_box: BoundingBox::from_points(cells.iter().copied()),
+        }
+    }
+}
+
+// Side of a cell on the border of its region
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Edge {
+    pub cell: Coord,
+    // Direction from the cell toward the outside of the region
+    pub outward: Direction,
+}
+
+impl Edge {
+    // Cell on the other side of the edge
+    pub fn facing(&self) -> Coord {
+        moved(&self.cell, &self.outward)
+    }
+
+    // Edge as a segment between cell corners, going clockwise around the region
+    // Corner Coord(x, y) is the top left corner of cell Coord(x, y)
+    fn segment(&self) -> (Coord, Coord, Direction) {
+        let Coord(x, y) = self.cell;
+        let (from, to) = match self.outward {
+            Direction::Up => (Coord(x, y), Coord(x + 1, y)),
+            Direction::Ri => (Coord(x + 1, y), Coord(x + 1, y + 1)),
+            Direction::Do => (Coord(x + 1, y + 1), Coord(x, y + 1)),
+            Direction::Le => (Coord(x, y + 1), Coord(x, y)),
+        };
+        (from, to, self.outward.rotate())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Corners {
+    // Corners pointing outward the region
+    pub convex: usize,
+    // Corners pointing inward the region
+    pub concave: usize,
+}
+
+impl Corners {
+    pub fn total(&self) -> usize {
+        self.convex + self.concave
+    }
+}
+
+// Closed polygon following the border of a region, through cell corners where it turns
+//
+// Outer borders go clockwise, holes go counterclockwise. Two cells of the region touching only
+// by a corner are considered apart: the border goes between them, so cells outside the region
+// touching by this corner end up in the same loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outline {
+    pub vertices: Vec<Coord>,
+}
+
+impl Outline {
+    // Area enclosed, negative for holes
+    pub fn signed_area(&self) -> isize {
+        self.vertices
+            .iter()
+            .zip(self.vertices.iter().cycle().skip(1))
+            .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+            .sum::<isize>()
+            / 2
+    }
+
+    pub fn is_hole(&self) -> bool {
+        self.signed_area() < 0
+    }
+}
+
+// Shape of a region, in the four directions neighbourhood
+pub struct RegionGeometry {
+    pub perimeter: Vec<Edge>,
+    pub sides: usize,
+    pub corners: Corners,
+    pub outlines: Vec<Outline>,
+}
+
+impl RegionGeometry {
+    pub fn holes(&self) -> usize {
+        self.outlines
+            .iter()
+            .filter(|outline| outline.is_hole())
+            .count()
+    }
+}
+
+static DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Ri, Direction::Do, Direction::Le];
+
+impl Regions {
+    pub fn geometry(&self, label: usize) -> RegionGeometry {
+        let inside = |c: &Coord| self.label_of(c) == Some(label);
+        let cells = &self.cells[label];
+
+        let perimeter = cells
+            .iter()
+            .flat_map(|cell| {
+                DIRECTIONS.map(|outward| Edge {
+                    cell: *cell,
+                    outward,
+                })
+            })
+            .filter(|edge| !inside(&edge.facing()))
+            .collect::<Vec<_>>();
+
+        // A side starts at each edge without a similar edge just before it, clockwise
+        let edges: HashSet<_> = perimeter.iter().copied().collect();
+        let sides = perimeter
+            .iter()
+            .filter(|edge| {
+                let before = Edge {
+                    cell: moved(&edge.cell, &edge.outward.rotate().rotate().rotate()),
+                    outward: edge.outward,
+                };
+                !edges.contains(&before)
+            })
+            .count();
+
+        let mut corners = Corners::default();
+        for cell in cells {
+            for dir in DIRECTIONS {
+                let (side, other_side) = (moved(cell, &dir), moved(cell, &dir.rotate()));
+                match (inside(&side), inside(&other_side)) {
+                    (false, false) => corners.convex += 1,
+                    (true, true) if !inside(&moved(&side, &dir.rotate())) => corners.concave += 1,
+                    _ => (),
+                }
+            }
+        }
+
+        RegionGeometry {
+            outlines: outlines(&perimeter),
+            perimeter,
+            sides,
+            corners,
+        }
+    }
+}
+
+// Chain edges into closed loops
+fn outlines(perimeter: &[Edge]) -> Vec<Outline> {
+    let segments = perimeter.iter().map(Edge::segment).collect::<Vec<_>>();
+    let mut starting_at = HashMap::<Coord, Vec<usize>>::new();
+    segments
+        .iter()
+        .enumerate()
+        .for_each(|(i, (from, _, _))| starting_at.entry(*from).or_default().push(i));
+
+    let mut used = vec![false; segments.len()];
+    let mut outlines = vec![];
+    for first in 0..segments.len() {
+        if used[first] {
+            continue;
+        }
+        let mut chain = vec![];
+        let mut current = Some(first);
+        while let Some(i) = current {
+            used[i] = true;
+            chain.push(i);
+            let (_, to, heading) = segments[i];
+            // When two segments go on, the region only touches itself by a corner here:
+            // turn right to stay around the current cell
+            let next = starting_at[&to].iter().filter(|j| !used[**j]);
+            current = next
+                .clone()
+                .find(|j| segments[**j].2 == heading.rotate())
+                .or(next.clone().next())
+                .copied();
+        }
+
+        // Only keep corners where the border turns
+        let vertices = chain
+            .iter()
+            .zip(chain.iter().cycle().skip(chain.len() - 1))
+            .filter(|(i, previous)| segments[**i].2 != segments[**previous].2)
+            .map(|(i, _)| segments[*i].0)
+            .collect();
+        outlines.push(Outline { vertices });
+    }
+    outlines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::components;
+    use crate::explore_2d::neighbors;
+    use crate::space2d::{BoundingBox, Coord, TableField};
+
+    #[test]
+    fn it_labels_regions_and_their_neighbours() {
+        let field =
+            TableField::from_rows(vec![vec![1, 1, 2], vec![3, 1, 2], vec![3, 3, 1]]).unwrap();
+        let regions = components(&field, &neighbors::FOUR, |(a, b): (&usize, &usize)| a == b);
+
+        assert_eq!(regions.len(), 4);
+        assert_eq!(regions.cells(0), [Coord(0, 0), Coord(1, 0), Coord(1, 1)]);
+        assert_eq!(regions.label_of(&Coord(2, 2)), Some(3));
+        assert_eq!(regions.adjacent(3).iter().collect::<Vec<_>>(), [&1, &2]);
+        assert_eq!(
+            regions.stats(2).bounding_box,
+            BoundingBox {
+                xmin: 0,
+                xmax: 2,
+                ymin: 1,
+                ymax: 3
+            }
+        );
+
+        let diagonal = components(&field, &neighbors::EIGHT, |(a, b): (&usize, &usize)| a == b);
+        assert_eq!(diagonal.len(), 3);
+        assert_eq!(diagonal.stats(0).area, 4);
+    }
+
+    #[test]
+    fn it_computes_region_geometry() {
+        // Region of 0 has a single hole made of two cells of 1 touching by a corner
+        let field = TableField::from_rows(vec![
+            vec![0, 0, 0, 0],
+            vec![0, 1, 0, 0],
+            vec![0, 0, 1, 0],
+            vec![0, 0, 0, 0],
+        ])
+        .unwrap();
+        let regions = components(&field, &neighbors::FOUR, |(a, b): (&usize, &usize)| a == b);
+
+        let ring = regions.geometry(0);
+        assert_eq!(ring.perimeter.len(), 16 + 8);
+        assert_eq!(ring.sides, 4 + 8);
+        assert_eq!(ring.corners.total(), ring.sides);
+        assert_eq!(ring.corners.convex, 6);
+        assert_eq!(ring.holes(), 1);
+        assert_eq!(ring.outlines[1].vertices.len(), 8);
+        assert_eq!(
+            ring.outlines[0].vertices,
+            [Coord(0, 0), Coord(4, 0), Coord(4, 4), Coord(0, 4)]
+        );
+        assert_eq!(ring.outlines[0].signed_area(), 16);
+
+        let dot = regions.geometry(1);
+        assert_eq!((dot.sides, dot.corners.convex, dot.holes()), (4, 4, 0));
+    }
+}
@@ -1,8 +1,129 @@
 use derive_more::derive::{Add, Sub};
+use std::cmp::Ordering;
+use std::ops::{AddAssign, Div, Mul, Neg, SubAssign};
+
+pub mod bits;
+pub mod load;
+pub mod pattern;
+pub mod render;
+pub mod view;
 
 #[derive(Debug, Copy, Clone, Add, Sub, Hash, PartialEq, Eq)]
 pub struct Coord(pub isize, pub isize);
 
+impl Coord {
+    pub fn manhattan(&self, other: &Coord) -> usize {
+        self.0.abs_diff(other.0) + self.1.abs_diff(other.1)
+    }
+
+    pub fn chebyshev(&self, other: &Coord) -> usize {
+        self.0.abs_diff(other.0).max(self.1.abs_diff(other.1))
+    }
+
+    pub fn signum(&self) -> Coord {
+        Coord(self.0.signum(), self.1.signum())
+    }
+
+    // Smallest step with the same direction, reaching every grid point along it
+    // Coord(4, -6) gives Coord(2, -3)
+    pub fn reduced(&self) -> Coord {
+        let (mut a, mut b) = (self.0.unsigned_abs(), self.1.unsigned_abs());
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        match a {
+            0 => *self,
+            gcd => *self / gcd as isize,
+        }
+    }
+
+    // Quarter turns, with y growing downward: clockwise turns right into down
+    pub fn rotate_cw(&self) -> Coord {
+        Coord(-self.1, self.0)
+    }
+
+    pub fn rotate_ccw(&self) -> Coord {
+        Coord(self.1, -self.0)
+    }
+}
+
+impl Mul<isize> for Coord {
+    type Output = Coord;
+
+    fn mul(self, rhs: isize) -> Coord {
+        Coord(self.0 * rhs, self.1 * rhs)
+    }
+}
+
+impl Div<isize> for Coord {
+    type Output = Coord;
+
+    fn div(self, rhs: isize) -> Coord {
+        Coord(self.0 / rhs, self.1 / rhs)
+    }
+}
+
+impl Neg for Coord {
+    type Output = Coord;
+
+    fn neg(self) -> Coord {
+        Coord(-self.0, -self.1)
+    }
+}
+
+impl AddAssign for Coord {
+    fn add_assign(&mut self, rhs: Coord) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Coord {
+    fn sub_assign(&mut self, rhs: Coord) {
+        *self = *self - rhs;
+    }
+}
+
+// Reading order: top to bottom, then left to right
+impl Ord for Coord {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.1, self.0).cmp(&(other.1, other.0))
+    }
+}
+
+impl PartialOrd for Coord {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<(isize, isize)> for Coord {
+    fn from((x, y): (isize, isize)) -> Self {
+        Coord(x, y)
+    }
+}
+
+impl From<Coord> for (isize, isize) {
+    fn from(Coord(x, y): Coord) -> Self {
+        (x, y)
+    }
+}
+
+impl TryFrom<(usize, usize)> for Coord {
+    type Error = std::num::TryFromIntError;
+
+    fn try_from((x, y): (usize, usize)) -> Result<Self, Self::Error> {
+        Ok(Coord(x.try_into()?, y.try_into()?))
+    }
+}
+
+impl TryFrom<Coord> for (usize, usize) {
+    type Error = std::num::TryFromIntError;
+
+    fn try_from(Coord(x, y): Coord) -> Result<Self, Self::Error> {
+        Ok((x.try_into()?, y.try_into()?))
+    }
+}
+
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
 pub struct BoundingBox {
     pub xmin: isize,
@@ -30,43 +151,426 @@ impl BoundingBox {
             ymax: h.try_into().ok()?,
         })
     }
+
+    // Map any coord into the box, as if opposite borders were stitched together
+    //
+    // The box must not be empty.
+    pub fn wrap(&self, coord: &Coord) -> Coord {
+        Coord(
+            self.xmin + (coord.0 - self.xmin).rem_euclid(self.xmax - self.xmin),
+            self.ymin + (coord.1 - self.ymin).rem_euclid(self.ymax - self.ymin),
+        )
+    }
+
+    // Smallest box containing all the points, empty if there is none
+    pub fn from_points(points: impl IntoIterator<Item = Coord>) -> Self {
+        points
+            .into_iter()
+            .map(|Coord(x, y)| BoundingBox {
+                xmin: x,
+                xmax: x + 1,
+                ymin: y,
+                ymax: y + 1,
+            })
+            .reduce(|acc, bb| acc.union(&bb))
+            .unwrap_or(BoundingBox {
+                xmin: 0,
+                xmax: 0,
+                ymin: 0,
+                ymax: 0,
+            })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.xmin >= self.xmax || self.ymin >= self.ymax
+    }
+
+    pub fn width(&self) -> usize {
+        (self.xmax - self.xmin).max(0) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.ymax - self.ymin).max(0) as usize
+    }
+
+    pub fn area(&self) -> usize {
+        self.width() * self.height()
+    }
+
+    // Smallest box containing both boxes, an empty box containing nothing
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        match (self.is_empty(), other.is_empty()) {
+            (true, _) => *other,
+            (_, true) => *self,
+            _ => BoundingBox {
+                xmin: self.xmin.min(other.xmin),
+                xmax: self.xmax.max(other.xmax),
+                ymin: self.ymin.min(other.ymin),
+                ymax: self.ymax.max(other.ymax),
+            },
+        }
+    }
+
+    pub fn intersection(&self, other: &BoundingBox) -> Option<BoundingBox> {
+        let inter = BoundingBox {
+            xmin: self.xmin.max(other.xmin),
+            xmax: self.xmax.min(other.xmax),
+            ymin: self.ymin.max(other.ymin),
+            ymax: self.ymax.min(other.ymax),
+        };
+        (!inter.is_empty()).then_some(inter)
+    }
+
+    // Grow each side of the box by `margin`
+    pub fn expand(&self, margin: usize) -> BoundingBox {
+        let margin = margin as isize;
+        BoundingBox {
+            xmin: self.xmin - margin,
+            xmax: self.xmax + margin,
+            ymin: self.ymin - margin,
+            ymax: self.ymax + margin,
+        }
+    }
+
+    // Move each side of the box inward by `margin`, collapsing to an empty box around the center
+    pub fn shrink(&self, margin: usize) -> BoundingBox {
+        let margin = margin as isize;
+        let shrink_range = |min: isize, max: isize| {
+            let center = min + (max - min) / 2;
+            ((min + margin).min(center), (max - margin).max(center))
+        };
+        let (xmin, xmax) = shrink_range(self.xmin, self.xmax);
+        let (ymin, ymax) = shrink_range(self.ymin, self.ymax);
+        BoundingBox {
+            xmin,
+            xmax,
+            ymin,
+            ymax,
+        }
+    }
+
+    // Nearest coord inside the box, which must not be empty
+    pub fn clamp(&self, coord: &Coord) -> Coord {
+        Coord(
+            coord.0.clamp(self.xmin, self.xmax - 1),
+            coord.1.clamp(self.ymin, self.ymax - 1),
+        )
+    }
+
+    // Coords from `from` by steps of `step`, as long as they are in the box
+    // A null step gives `from` once
+    pub fn ray(&self, from: Coord, step: Coord) -> impl Iterator<Item = Coord> {
+        let area = *self;
+        let moving = step != Coord(0, 0);
+        std::iter::successors(Some(from), move |coord| moving.then(|| *coord + step))
+            .take_while(move |coord| area.contains(coord))
+    }
+
+    // Row major iteration: left to right, then top to bottom
+    pub fn iter(&self) -> impl Iterator<Item = Coord> {
+        let BoundingBox {
+            xmin,
+            xmax,
+            ymin,
+            ymax,
+        } = *self;
+        (ymin..ymax).flat_map(move |y| (xmin..xmax).map(move |x| Coord(x, y)))
+    }
+
+    // Column major iteration: top to bottom, then left to right
+    pub fn iter_columns(&self) -> impl Iterator<Item = Coord> {
+        let BoundingBox {
+            xmin,
+            xmax,
+            ymin,
+            ymax,
+        } = *self;
+        (xmin..xmax).flat_map(move |x| (ymin..ymax).map(move |y| Coord(x, y)))
+    }
+
+    // Corner cells, clockwise from the top left one. The box must not be empty
+    pub fn corners(&self) -> [Coord; 4] {
+        [
+            Coord(self.xmin, self.ymin),
+            Coord(self.xmax - 1, self.ymin),
+            Coord(self.xmax - 1, self.ymax - 1),
+            Coord(self.xmin, self.ymax - 1),
+        ]
+    }
+
+    // Cells along the border, clockwise from the top left corner, each of them once
+    pub fn edges(&self) -> Vec<Coord> {
+        if self.is_empty() {
+            return vec![];
+        }
+        let [top_left, top_right, bottom_right, bottom_left] = self.corners();
+        let mut edges = vec![];
+        edges.extend((top_left.0..=top_right.0).map(|x| Coord(x, top_left.1)));
+        edges.extend((top_right.1 + 1..=bottom_right.1).map(|y| Coord(top_right.0, y)));
+        if bottom_left.1 != top_left.1 {
+            edges.extend(
+                (bottom_left.0..bottom_right.0)
+                    .rev()
+                    .map(|x| Coord(x, bottom_left.1)),
+            );
+        }
+        if bottom_left.0 != bottom_right.0 {
+            edges.extend(
+                (top_left.1 + 1..bottom_left.1)
+                    .rev()
+                    .map(|y| Coord(top_left.0, y)),
+            );
+        }
+        edges
+    }
 }
 
 pub trait Field {
     type Out;
     #[allow(dead_code)]
     fn get(&self, coords: &Coord) -> Option<&Self::Out>;
+    fn definition_area(&self) -> &BoundingBox;
+
+    // Coord of the definition area standing for the same cell, None if there is none
+    fn canonical(&self, coords: &Coord) -> Option<Coord> {
+        self.definition_area().contains(coords).then_some(*coords)
+    }
+
+    // Cells having a value, in reading order
+    fn iter(&self) -> impl Iterator<Item = (Coord, &Self::Out)> {
+        self.definition_area()
+            .iter()
+            .filter_map(|coord| Some((coord, self.get(&coord)?)))
+    }
+
+    // Table of the mapped values over the same definition area
+    //
+    // Panics if a cell of the definition area has no value, `iter` skips such cells instead.
+    fn map<U>(&self, mut f: impl FnMut(&Self::Out) -> U) -> TableField<U> {
+        TableField::from_fn(*self.definition_area(), |coord| {
+            f(self
+                .get(&coord)
+                .expect("Cell without value in its field definition area"))
+        })
+    }
+
+    // Combine values of both fields, on the intersection of their definition areas
+    //
+    // Panics as `map` does if a cell of this intersection has no value in either field.
+    fn zip_with<G: Field, U>(
+        &self,
+        other: &G,
+        mut f: impl FnMut(&Self::Out, &G::Out) -> U,
+    ) -> TableField<U> {
+        let area = self
+            .definition_area()
+            .intersection(other.definition_area())
+            .unwrap_or(BoundingBox::from_points([]));
+        TableField::from_fn(area, |coord| match (self.get(&coord), other.get(&coord)) {
+            (Some(a), Some(b)) => f(a, b),
+            _ => panic!("Cell without value in its field definition area"),
+        })
+    }
+
+    // First cell matching, in reading order
+    fn find(&self, mut pred: impl FnMut(&Self::Out) -> bool) -> Option<Coord> {
+        self.iter()
+            .find(|(_, value)| pred(value))
+            .map(|(coord, _)| coord)
+    }
+
+    fn positions(&self, mut pred: impl FnMut(&Self::Out) -> bool) -> impl Iterator<Item = Coord> {
+        self.iter()
+            .filter(move |(_, value)| pred(value))
+            .map(|(coord, _)| coord)
+    }
+
+    fn count(&self, mut pred: impl FnMut(&Self::Out) -> bool) -> usize {
+        self.iter().filter(|(_, value)| pred(value)).count()
+    }
+
+    // Neighbours having a value, in the order of the neighbourhood
+    fn neighbours_of<'a>(
+        &'a self,
+        coord: &Coord,
+        neighbourhood: &'a [Coord],
+    ) -> impl Iterator<Item = (Coord, &'a Self::Out)> {
+        let coord = *coord;
+        neighbourhood
+            .iter()
+            .filter_map(move |delta| Some((coord + *delta, self.get(&(coord + *delta))?)))
+    }
+
+    // Cells from `from` by steps of `step`, until one has no value. A null step gives `from` once
+    fn ray(&self, from: &Coord, step: Coord) -> impl Iterator<Item = (Coord, &Self::Out)> {
+        self.definition_area()
+            .ray(*from, step)
+            .map_while(|coord| Some((coord, self.get(&coord)?)))
+    }
+
+    // Same as `ray`, also ending with the first cell for which `stop` holds
+    fn ray_until(
+        &self,
+        from: &Coord,
+        step: Coord,
+        mut stop: impl FnMut(&Self::Out) -> bool,
+    ) -> impl Iterator<Item = (Coord, &Self::Out)> {
+        let mut stopped = false;
+        self.ray(from, step).take_while(move |(_, value)| {
+            let go_on = !stopped;
+            stopped = stopped || stop(value);
+            go_on
+        })
+    }
+
+    // First cell after `from` in this direction for which `pred` holds, None for a null step
+    fn first_hit(
+        &self,
+        from: &Coord,
+        step: Coord,
+        mut pred: impl FnMut(&Self::Out) -> bool,
+    ) -> Option<(Coord, &Self::Out)> {
+        self.ray(from, step).skip(1).find(|(_, value)| pred(value))
+    }
+
+    // Cells seen from `from` looking along each direction, `from` excluded
+    // Opaque cells hide what is behind them but are visible themselves. Null steps see nothing
+    fn visible_from(
+        &self,
+        from: &Coord,
+        directions: &[Coord],
+        mut opaque: impl FnMut(&Self::Out) -> bool,
+    ) -> Vec<(Coord, &Self::Out)> {
+        directions
+            .iter()
+            .filter(|step| **step != Coord(0, 0))
+            .flat_map(|step| {
+                self.ray_until(&(*from + *step), *step, &mut opaque)
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+// Fields whose values can be changed in place
+pub trait FieldMut: Field {
     #[allow(dead_code)]
     fn get_mut(&mut self, coords: &Coord) -> Option<&mut Self::Out>;
-    fn definition_area(&self) -> &BoundingBox;
+
+    // Mutable counterpart of `iter`, going through `get_mut` for each cell in reading order
+    //
+    // It can't be an iterator of mutable borrows for every field: views such as `view::Scaled`
+    // reach the same cell from several coords. `TableField::iter_mut` is one where it can.
+    fn for_each_mut(&mut self, mut f: impl FnMut(Coord, &mut Self::Out)) {
+        for coord in self.definition_area().iter() {
+            if let Some(value) = self.get_mut(&coord) {
+                f(coord, value);
+            }
+        }
+    }
+}
+
+// Shared borrows let views be built over a field without taking it, but are read only
+impl<F: Field> Field for &F {
+    type Out = F::Out;
+
+    fn get(&self, coords: &Coord) -> Option<&Self::Out> {
+        (**self).get(coords)
+    }
+
+    fn definition_area(&self) -> &BoundingBox {
+        (**self).definition_area()
+    }
+
+    fn canonical(&self, coords: &Coord) -> Option<Coord> {
+        (**self).canonical(coords)
+    }
+}
+
+impl<F: Field> Field for &mut F {
+    type Out = F::Out;
+
+    fn get(&self, coords: &Coord) -> Option<&Self::Out> {
+        (**self).get(coords)
+    }
+
+    fn definition_area(&self) -> &BoundingBox {
+        (**self).definition_area()
+    }
+
+    fn canonical(&self, coords: &Coord) -> Option<Coord> {
+        (**self).canonical(coords)
+    }
+}
+
+impl<F: FieldMut> FieldMut for &mut F {
+    fn get_mut(&mut self, coords: &Coord) -> Option<&mut Self::Out> {
+        (**self).get_mut(coords)
+    }
 }
 
 use huparse::table::Table;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TableField<T> {
     bounding_box: BoundingBox,
-    values: Table<T>,
+    // Row major
+    values: Vec<T>,
 }
 
+#[derive(Debug)]
 pub enum TableFieldError {
     TableTooBig,
+    RaggedRows { line: usize },
+}
+
+impl<T> TableField<T> {
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self, TableFieldError> {
+        use TableFieldError::*;
+        let width = rows.first().map(|row| row.len()).unwrap_or(0);
+        if let Some(line) = rows.iter().position(|row| row.len() != width) {
+            return Err(RaggedRows { line });
+        }
+        let bounding_box = BoundingBox::try_from_size(width, rows.len()).ok_or(TableTooBig)?;
+        Ok(TableField {
+            bounding_box,
+            values: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    pub fn from_fn(bounding_box: BoundingBox, mut f: impl FnMut(Coord) -> T) -> Self {
+        let values = bounding_box.iter().map(&mut f).collect();
+        TableField {
+            bounding_box,
+            values,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Coord, &mut T)> {
+        self.bounding_box.iter().zip(self.values.iter_mut())
+    }
+
+    fn index(&self, coords: &Coord) -> Option<usize> {
+        let width = self.bounding_box.width();
+        self.bounding_box
+            .inside_coords(coords)
+            .map(|(x, y)| y * width + x)
+    }
+}
+
+impl<T: Clone> TableField<T> {
+    pub fn filled(bounding_box: BoundingBox, value: T) -> Self {
+        Self::from_fn(bounding_box, |_| value.clone())
+    }
 }
 
 impl<T> TryFrom<Table<T>> for TableField<T> {
     type Error = TableFieldError;
 
     fn try_from(value: Table<T>) -> Result<Self, Self::Error> {
-        use TableFieldError::*;
-        let valid_width: isize = value.width().try_into().map_err(|_| TableTooBig)?;
-        let valid_height: isize = value.height().try_into().map_err(|_| TableTooBig)?;
-        Ok(TableField {
-            values: value,
-            bounding_box: BoundingBox {
-                xmin: 0,
-                xmax: valid_width,
-                ymin: 0,
-                ymax: valid_height,
-            },
-        })
+        let (_, _, rows) = value.into_tuple();
+        Self::from_rows(rows)
     }
 }
 
@@ -74,15 +578,131 @@ impl<T> Field for TableField<T> {
     type Out = T;
 
     fn get(&self, coords: &Coord) -> Option<&Self::Out> {
-        self.definition_area()
-            .inside_coords(coords)
-            .and_then(|(x, y)| self.values.get(y, x))
+        self.index(coords).and_then(|i| self.values.get(i))
+    }
+
+    fn definition_area(&self) -> &BoundingBox {
+        &self.bounding_box
+    }
+
+    // Values are already stored in reading order
+    fn iter(&self) -> impl Iterator<Item = (Coord, &Self::Out)> {
+        self.bounding_box.iter().zip(self.values.iter())
+    }
+
+    // Coords are only computed for the values fulfilling `pred`
+    fn positions(&self, mut pred: impl FnMut(&Self::Out) -> bool) -> impl Iterator<Item = Coord> {
+        let BoundingBox { xmin, ymin, .. } = self.bounding_box;
+        let width = self.bounding_box.width();
+        self.values
+            .iter()
+            .enumerate()
+            .filter(move |(_, value)| pred(value))
+            .map(move |(i, _)| Coord(xmin + (i % width) as isize, ymin + (i / width) as isize))
     }
+}
 
+impl<T> FieldMut for TableField<T> {
     fn get_mut(&mut self, coords: &Coord) -> Option<&mut Self::Out> {
-        self.definition_area()
-            .inside_coords(coords)
-            .and_then(|(x, y)| self.values.get_mut(y, x))
+        self.index(coords).and_then(|i| self.values.get_mut(i))
+    }
+}
+
+// Field where only values different from a default one are stored
+//
+// Coords inside the definition area but without stored value yield the default value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseField<T> {
+    bounding_box: BoundingBox,
+    default: T,
+    values: HashMap<Coord, T>,
+}
+
+impl<T> SparseField<T> {
+    // Empty field over an explicit definition area
+    pub fn new(bounding_box: BoundingBox, default: T) -> Self {
+        SparseField {
+            bounding_box,
+            default,
+            values: HashMap::new(),
+        }
+    }
+
+    // Field whose definition area is the smallest one containing all the given values
+    pub fn from_values(values: impl IntoIterator<Item = (Coord, T)>, default: T) -> Self {
+        let values: HashMap<_, _> = values.into_iter().collect();
+        let bounding_box = BoundingBox::from_points(values.keys().copied());
+        SparseField {
+            bounding_box,
+            default,
+            values,
+        }
+    }
+
+    pub fn default_value(&self) -> &T {
+        &self.default
+    }
+
+    // Store a value inside the definition area, giving it back as an error outside of it
+    pub fn insert(&mut self, coord: Coord, value: T) -> Result<Option<T>, T> {
+        if !self.bounding_box.contains(&coord) {
+            return Err(value);
+        }
+        Ok(self.values.insert(coord, value))
+    }
+
+    // Store a value, growing the definition area if the coord lies outside of it
+    pub fn insert_growing(&mut self, coord: Coord, value: T) -> Option<T> {
+        self.bounding_box = self.bounding_box.union(&BoundingBox::from_points([coord]));
+        self.values.insert(coord, value)
+    }
+
+    // Remove the stored value, the coord gets back to the default value
+    pub fn remove(&mut self, coord: &Coord) -> Option<T> {
+        self.values.remove(coord)
+    }
+
+    // Coords holding a stored value
+    pub fn stored(&self) -> impl Iterator<Item = (&Coord, &T)> {
+        self.values.iter()
+    }
+}
+
+impl<T: PartialEq> SparseField<T> {
+    // Keep only values of the table different from `default`
+    pub fn from_table(table: TableField<T>, default: T) -> Self {
+        let bounding_box = table.bounding_box;
+        let values = bounding_box
+            .iter()
+            .zip(table.values)
+            .filter(|(_, value)| *value != default)
+            .collect();
+        SparseField {
+            bounding_box,
+            default,
+            values,
+        }
+    }
+}
+
+impl<T: Clone> From<SparseField<T>> for TableField<T> {
+    fn from(mut sparse: SparseField<T>) -> Self {
+        TableField::from_fn(sparse.bounding_box, |c| {
+            sparse
+                .values
+                .remove(&c)
+                .unwrap_or_else(|| sparse.default.clone())
+        })
+    }
+}
+
+impl<T: Clone> Field for SparseField<T> {
+    type Out = T;
+
+    fn get(&self, coords: &Coord) -> Option<&Self::Out> {
+        self.bounding_box
+            .contains(coords)
+            .then(|| self.values.get(coords).unwrap_or(&self.default))
     }
 
     fn definition_area(&self) -> &BoundingBox {
@@ -90,6 +710,60 @@ impl<T> Field for TableField<T> {
     }
 }
 
+impl<T: Clone> FieldMut for SparseField<T> {
+    fn get_mut(&mut self, coords: &Coord) -> Option<&mut Self::Out> {
+        if self.bounding_box.contains(coords) {
+            Some(
+                self.values
+                    .entry(*coords)
+                    .or_insert_with(|| self.default.clone()),
+            )
+        } else {
+            None
+        }
+    }
+}
+
+// Periodic view of a field: every coord is defined, and wrapped into the inner definition area
+//
+// The inner definition area must not be empty.
+pub struct WrappingField<F: Field> {
+    inner: F,
+}
+
+impl<F: Field> WrappingField<F> {
+    pub fn new(inner: F) -> Self {
+        WrappingField { inner }
+    }
+
+    pub fn into_inner(self) -> F {
+        self.inner
+    }
+}
+
+impl<F: Field> Field for WrappingField<F> {
+    type Out = F::Out;
+
+    fn get(&self, coords: &Coord) -> Option<&Self::Out> {
+        self.inner.get(&self.inner.definition_area().wrap(coords))
+    }
+
+    fn definition_area(&self) -> &BoundingBox {
+        self.inner.definition_area()
+    }
+
+    fn canonical(&self, coords: &Coord) -> Option<Coord> {
+        Some(self.inner.definition_area().wrap(coords))
+    }
+}
+
+impl<F: FieldMut> FieldMut for WrappingField<F> {
+    fn get_mut(&mut self, coords: &Coord) -> Option<&mut Self::Out> {
+        let wrapped = self.inner.definition_area().wrap(coords);
+        self.inner.get_mut(&wrapped)
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Direction {
     Up,
@@ -117,3 +791,351 @@ impl Direction {
         }
     }
 }
+
+// Cardinal and diagonal directions, in clockwise order
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Direction8 {
+    Up,
+    UpRi,
+    Ri,
+    DoRi,
+    Do,
+    DoLe,
+    Le,
+    UpLe,
+}
+
+impl Direction8 {
+    pub const ALL: [Direction8; 8] = [
+        Direction8::Up,
+        Direction8::UpRi,
+        Direction8::Ri,
+        Direction8::DoRi,
+        Direction8::Do,
+        Direction8::DoLe,
+        Direction8::Le,
+        Direction8::UpLe,
+    ];
+
+    fn turned(self, eighths: usize) -> Direction8 {
+        Self::ALL[(self as usize + eighths) % 8]
+    }
+
+    // Turn by 45°
+    pub fn rotate_cw(self) -> Direction8 {
+        self.turned(1)
+    }
+
+    // Turn by -45°
+    pub fn rotate_ccw(self) -> Direction8 {
+        self.turned(7)
+    }
+
+    pub fn opposite(self) -> Direction8 {
+        self.turned(4)
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        self as usize % 2 == 1
+    }
+
+    // Move of one step in this direction, with y growing downward
+    pub fn to_delta(self) -> Coord {
+        match self {
+            Direction8::Up => Coord(0, -1),
+            Direction8::UpRi => Coord(1, -1),
+            Direction8::Ri => Coord(1, 0),
+            Direction8::DoRi => Coord(1, 1),
+            Direction8::Do => Coord(0, 1),
+            Direction8::DoLe => Coord(-1, 1),
+            Direction8::Le => Coord(-1, 0),
+            Direction8::UpLe => Coord(-1, -1),
+        }
+    }
+}
+
+impl From<Direction> for Direction8 {
+    fn from(dir: Direction) -> Self {
+        match dir {
+            Direction::Up => Direction8::Up,
+            Direction::Ri => Direction8::Ri,
+            Direction::Do => Direction8::Do,
+            Direction::Le => Direction8::Le,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::view::Scaled;
+    use super::{
+        BoundingBox, Coord, Direction, Direction8, Field, FieldMut, SparseField, TableField,
+        WrappingField,
+    };
+    use crate::explore_2d::neighbors;
+
+    #[test]
+    fn it_grows_sparse_fields() {
+        let mut sparse = SparseField::from_values([(Coord(1, 2), 'a'), (Coord(3, 0), 'b')], '.');
+        let inferred = BoundingBox {
+            xmin: 1,
+            xmax: 4,
+            ymin: 0,
+            ymax: 3,
+        };
+        assert_eq!(sparse.definition_area(), &inferred);
+        assert_eq!(sparse.get(&Coord(2, 1)), Some(&'.'));
+        assert_eq!(sparse.get(&Coord(0, 0)), None);
+
+        assert_eq!(sparse.insert(Coord(-1, 4), 'c'), Err('c'));
+        assert_eq!(sparse.definition_area(), &inferred);
+        assert_eq!(sparse.insert(Coord(2, 1), 'c'), Ok(None));
+        assert_eq!(sparse.insert(Coord(2, 1), 'd'), Ok(Some('c')));
+
+        assert_eq!(sparse.insert_growing(Coord(-1, 4), 'c'), None);
+        let grown = BoundingBox {
+            xmin: -1,
+            xmax: 4,
+            ymin: 0,
+            ymax: 5,
+        };
+        assert_eq!(sparse.definition_area(), &grown);
+        assert_eq!(sparse.get(&Coord(-1, 4)), Some(&'c'));
+        assert_eq!(sparse.remove(&Coord(-1, 4)), Some('c'));
+        assert_eq!(sparse.get(&Coord(-1, 4)), Some(&'.'));
+
+        let empty = SparseField::from_values([], 0);
+        assert!(empty.definition_area().is_empty());
+    }
+
+    #[test]
+    fn it_converts_sparse_fields_to_tables_and_back() {
+        let table = TableField::from_rows(vec![vec![0, 1, 0], vec![0, 0, 2]]).unwrap();
+        let sparse = SparseField::from_table(table.clone(), 0);
+        assert_eq!(sparse.stored().count(), 2);
+        assert_eq!(sparse.get(&Coord(2, 1)), Some(&2));
+        assert_eq!(TableField::from(sparse), table);
+    }
+
+    #[test]
+    fn it_wraps_coords() {
+        let bb = BoundingBox {
+            xmin: 2,
+            xmax: 5,
+            ymin: -1,
+            ymax: 1,
+        };
+        assert_eq!(bb.wrap(&Coord(3, 0)), Coord(3, 0));
+        assert_eq!(bb.wrap(&Coord(5, 1)), Coord(2, -1));
+        assert_eq!(bb.wrap(&Coord(1, -2)), Coord(4, 0));
+        assert_eq!(bb.wrap(&Coord(-7, -6)), Coord(2, 0));
+        // Ten periods right, seven periods down
+        assert_eq!(bb.wrap(&Coord(33, 14)), Coord(3, 0));
+
+        let table = TableField::from_rows(vec![vec!['a', 'b'], vec!['c', 'd']]).unwrap();
+        let mut wrapping = WrappingField::new(table);
+        assert_eq!(wrapping.get(&Coord(-1, -1)), Some(&'d'));
+        assert_eq!(wrapping.get(&Coord(4, 7)), Some(&'c'));
+        assert_eq!(wrapping.canonical(&Coord(4, 7)), Some(Coord(0, 1)));
+        assert_eq!(wrapping.inner.canonical(&Coord(4, 7)), None);
+        *wrapping.get_mut(&Coord(-3, 2)).unwrap() = 'z';
+        assert_eq!(wrapping.into_inner().get(&Coord(1, 0)), Some(&'z'));
+    }
+
+    #[test]
+    fn it_turns_eight_ways() {
+        for dir in Direction8::ALL {
+            assert_eq!(dir.rotate_cw().rotate_ccw(), dir);
+            assert_eq!(dir.rotate_ccw().rotate_cw(), dir);
+            assert_eq!(dir.opposite().opposite(), dir);
+            assert_eq!(dir.opposite().to_delta(), -dir.to_delta());
+            assert_eq!((0..4).fold(dir, |d, _| d.rotate_cw()), dir.opposite());
+        }
+        let deltas = Direction8::ALL.map(Direction8::to_delta);
+        assert!(neighbors::EIGHT.iter().all(|d| deltas.contains(d)));
+        assert_eq!(Direction8::from(Direction::Le).to_delta(), Coord(-1, 0));
+        assert!(Direction8::DoLe.is_diagonal());
+        assert!(!Direction8::Do.is_diagonal());
+    }
+
+    #[test]
+    fn it_computes_with_coords() {
+        assert_eq!(Coord(4, -6).reduced(), Coord(2, -3));
+        assert_eq!(Coord(0, -6).reduced(), Coord(0, -1));
+        assert_eq!(Coord(0, 0).reduced(), Coord(0, 0));
+        assert_eq!(Coord(1, 0).rotate_cw(), Coord(0, 1));
+        assert_eq!(Coord(1, 0).rotate_ccw(), Coord(0, -1));
+        assert_eq!(Coord(3, -2).rotate_cw().rotate_ccw(), Coord(3, -2));
+        assert_eq!(Coord(1, 2).manhattan(&Coord(-1, 0)), 4);
+        assert_eq!(Coord(1, 2).chebyshev(&Coord(-1, 5)), 3);
+
+        let mut sorted = vec![Coord(0, 1), Coord(5, 0), Coord(-1, 1), Coord(0, 0)];
+        sorted.sort();
+        assert_eq!(
+            sorted,
+            [Coord(0, 0), Coord(5, 0), Coord(-1, 1), Coord(0, 1)]
+        );
+
+        assert_eq!(Coord::try_from((2usize, 3usize)), Ok(Coord(2, 3)));
+        assert!(Coord::try_from((usize::MAX, 0usize)).is_err());
+        assert_eq!(<(usize, usize)>::try_from(Coord(2, 3)), Ok((2, 3)));
+        assert!(<(usize, usize)>::try_from(Coord(-1, 0)).is_err());
+    }
+
+    fn bb(xmin: isize, xmax: isize, ymin: isize, ymax: isize) -> BoundingBox {
+        BoundingBox {
+            xmin,
+            xmax,
+            ymin,
+            ymax,
+        }
+    }
+
+    #[test]
+    fn it_combines_bounding_boxes() {
+        let a = bb(0, 4, 0, 3);
+        let b = bb(2, 6, -1, 1);
+        assert_eq!(a.union(&b), bb(0, 6, -1, 3));
+        assert_eq!(a.intersection(&b), Some(bb(2, 4, 0, 1)));
+        assert_eq!(a.intersection(&bb(4, 6, 0, 3)), None);
+        let empty = BoundingBox::from_points([]);
+        assert_eq!(a.union(&empty), a);
+        assert_eq!(empty.union(&a), a);
+
+        assert_eq!(bb(0, 5, 0, 4).shrink(1), bb(1, 4, 1, 3));
+        assert!(bb(0, 5, 0, 4).shrink(3).is_empty());
+        assert_eq!(bb(0, 5, 0, 4).expand(2).shrink(2), bb(0, 5, 0, 4));
+    }
+
+    #[test]
+    fn it_walks_along_bounding_box_edges() {
+        let square = bb(0, 3, 0, 3).edges();
+        assert_eq!(square.len(), 8);
+        assert_eq!(
+            square[..4],
+            [Coord(0, 0), Coord(1, 0), Coord(2, 0), Coord(2, 1)]
+        );
+        assert_eq!(square[7], Coord(0, 1));
+
+        let column = [Coord(1, 0), Coord(1, 1), Coord(1, 2)];
+        assert_eq!(bb(1, 2, 0, 3).edges(), column);
+        let row = [Coord(0, 5), Coord(1, 5), Coord(2, 5)];
+        assert_eq!(bb(0, 3, 5, 6).edges(), row);
+        assert_eq!(bb(0, 1, 0, 1).edges(), [Coord(0, 0)]);
+        assert!(bb(0, 0, 0, 3).edges().is_empty());
+    }
+
+    #[test]
+    fn it_combines_fields() {
+        let table = TableField::from_rows(vec![vec![1, 2], vec![3, 4]]).unwrap();
+        let values = table.iter().map(|(_, v)| *v).collect::<Vec<_>>();
+        assert_eq!(values, [1, 2, 3, 4]);
+        assert_eq!(table.iter().nth(2).map(|(c, _)| c), Some(Coord(0, 1)));
+        assert_eq!(table.find(|v| *v > 2), Some(Coord(0, 1)));
+        assert_eq!(table.find(|v| *v > 4), None);
+        assert_eq!(table.count(|v| v % 2 == 0), 2);
+        let odd = table.positions(|v| v % 2 == 1).collect::<Vec<_>>();
+        assert_eq!(odd, [Coord(0, 0), Coord(0, 1)]);
+
+        let tens = table.map(|v| v * 10);
+        assert_eq!(tens.get(&Coord(1, 1)), Some(&40));
+        let shifted = SparseField::from_values([(Coord(1, 1), 5), (Coord(2, 2), 0)], 0);
+        let sums = table.zip_with(&shifted, |a, b| a + b);
+        assert_eq!(sums.definition_area(), &bb(1, 2, 1, 2));
+        assert_eq!(sums.get(&Coord(1, 1)), Some(&9));
+
+        let around = table.neighbours_of(&Coord(0, 0), &neighbors::FOUR);
+        assert_eq!(
+            around.collect::<Vec<_>>(),
+            [(Coord(1, 0), &2), (Coord(0, 1), &3)]
+        );
+    }
+
+    // Defined over a 2x2 area, but only holding a value at Coord(0, 0)
+    struct Holed;
+
+    impl Field for Holed {
+        type Out = usize;
+
+        fn get(&self, coords: &Coord) -> Option<&usize> {
+            (*coords == Coord(0, 0)).then_some(&1)
+        }
+
+        fn definition_area(&self) -> &BoundingBox {
+            static AREA: BoundingBox = BoundingBox {
+                xmin: 0,
+                xmax: 2,
+                ymin: 0,
+                ymax: 2,
+            };
+            &AREA
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Cell without value in its field definition area")]
+    fn it_refuses_to_map_cells_without_value() {
+        assert_eq!(Holed.iter().count(), 1);
+        Holed.map(|v| v * 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cell without value in its field definition area")]
+    fn it_refuses_to_zip_cells_without_value() {
+        let table = TableField::filled(*Holed.definition_area(), 0);
+        table.zip_with(&Holed, |a, b| a + b);
+    }
+
+    #[test]
+    fn it_updates_every_cell() {
+        let mut table = TableField::from_rows(vec![vec![1, 2], vec![3, 4]]).unwrap();
+        table.iter_mut().for_each(|(_, v)| *v += 1);
+        assert_eq!(table.get(&Coord(1, 1)), Some(&5));
+
+        let mut sparse = SparseField::new(bb(0, 2, 0, 2), 0);
+        sparse.for_each_mut(|c, v| *v = c.0 + c.1);
+        assert_eq!(sparse.get(&Coord(1, 1)), Some(&2));
+
+        // Each cell of the table is reached twice through the view
+        let mut scaled = Scaled::new(&mut table, 2, 1);
+        scaled.for_each_mut(|_, v| *v *= 10);
+        assert_eq!(table.get(&Coord(0, 0)), Some(&200));
+    }
+
+    #[test]
+    fn it_casts_rays() {
+        let rows = ["a.#b", "#..."].map(|row| row.chars().collect());
+        let field = TableField::from_rows(rows.into()).unwrap();
+        let right = Coord(1, 0);
+        assert_eq!(field.ray(&Coord(0, 0), right).count(), 4);
+        assert_eq!(field.ray(&Coord(0, 0), Coord(1, 1)).count(), 2);
+        assert_eq!(field.ray(&Coord(5, 0), right).count(), 0);
+        let still = field.ray(&Coord(1, 0), Coord(0, 0)).collect::<Vec<_>>();
+        assert_eq!(still, [(Coord(1, 0), &'.')]);
+        assert_eq!(field.ray(&Coord(5, 0), Coord(0, 0)).count(), 0);
+        let never = field.ray_until(&Coord(1, 0), Coord(0, 0), |_| false);
+        assert_eq!(never.count(), 1);
+        let area = field.definition_area();
+        let still = area.ray(Coord(3, 1), Coord(0, 0)).collect::<Vec<_>>();
+        assert_eq!(still, [Coord(3, 1)]);
+        let until_wall = field.ray_until(&Coord(0, 0), right, |c| *c == '#');
+        assert_eq!(until_wall.map(|(_, c)| *c).collect::<String>(), "a.#");
+
+        let hit = field.first_hit(&Coord(0, 0), right, |c| *c != '.');
+        assert_eq!(hit, Some((Coord(2, 0), &'#')));
+        assert_eq!(field.first_hit(&Coord(2, 0), right, |c| *c == 'a'), None);
+        assert_eq!(
+            field.first_hit(&Coord(0, 0), Coord(0, 0), |c| *c == 'a'),
+            None
+        );
+
+        // Looking around from the wall, `a` ends the ray going left
+        let visible = field.visible_from(&Coord(2, 0), &neighbors::EIGHT, |c| *c != '.');
+        let mut seen = visible.iter().map(|(c, _)| *c).collect::<Vec<_>>();
+        seen.sort();
+        let expected = [(0, 0), (1, 0), (3, 0), (1, 1), (2, 1), (3, 1)].map(Coord::from);
+        assert_eq!(seen, expected);
+        let null = field.visible_from(&Coord(2, 0), &[Coord(0, 0), right], |c| *c != '.');
+        assert_eq!(null, [(Coord(3, 0), &'b')]);
+    }
+}
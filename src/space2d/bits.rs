@@ -0,0 +1,208 @@
+use super::{BoundingBox, Coord, Direction, Field};
+
+const WORD: usize = u64::BITS as usize;
+
+// Lines of bits of the same length, each one starting on a new word
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BitLines {
+    words_per_line: usize,
+    words: Vec<u64>,
+}
+
+impl BitLines {
+    fn new(lines: usize, len: usize) -> Self {
+        let words_per_line = len.div_ceil(WORD);
+        BitLines {
+            words_per_line,
+            words: vec![0; lines * words_per_line],
+        }
+    }
+
+    fn get(&self, line: usize, pos: usize) -> bool {
+        self.words[line * self.words_per_line + pos / WORD] & (1 << (pos % WORD)) != 0
+    }
+
+    fn set(&mut self, line: usize, pos: usize, value: bool) {
+        let word = &mut self.words[line * self.words_per_line + pos / WORD];
+        if value {
+            *word |= 1 << (pos % WORD);
+        } else {
+            *word &= !(1 << (pos % WORD));
+        }
+    }
+
+    // First set bit at or after `start`
+    fn first_from(&self, line: usize, start: usize) -> Option<usize> {
+        let words = &self.words[line * self.words_per_line..(line + 1) * self.words_per_line];
+        let mut w = start / WORD;
+        let mut word = words.get(w)? & (!0 << (start % WORD));
+        loop {
+            if word != 0 {
+                return Some(w * WORD + word.trailing_zeros() as usize);
+            }
+            w += 1;
+            word = *words.get(w)?;
+        }
+    }
+
+    // Last set bit strictly before `end`
+    fn last_before(&self, line: usize, end: usize) -> Option<usize> {
+        let words = &self.words[line * self.words_per_line..(line + 1) * self.words_per_line];
+        let last = end.checked_sub(1)?;
+        let mut w = last / WORD;
+        let mut word = words[w] & (!0 >> (WORD - 1 - last % WORD));
+        loop {
+            if word != 0 {
+                return Some(w * WORD + WORD - 1 - word.leading_zeros() as usize);
+            }
+            w = w.checked_sub(1)?;
+            word = words[w];
+        }
+    }
+}
+
+// Boolean field storing one bit per cell
+//
+// Bits are kept both per row and per column, so scans are fast in any direction.
+// Bits can't be borrowed mutably, so it is no `FieldMut`: use `set` instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitField {
+    bounding_box: BoundingBox,
+    rows: BitLines,
+    columns: BitLines,
+}
+
+impl BitField {
+    pub fn new(bounding_box: BoundingBox) -> Self {
+        let (width, height) = (bounding_box.width(), bounding_box.height());
+        BitField {
+            bounding_box,
+            rows: BitLines::new(height, width),
+            columns: BitLines::new(width, height),
+        }
+    }
+
+    // Cells of the field for which `pred` holds are set
+    pub fn from_field<F: Field>(field: &F, mut pred: impl FnMut(&F::Out) -> bool) -> Self {
+        let mut bits = Self::new(*field.definition_area());
+        for coord in field.positions(|value| pred(value)) {
+            bits.set(&coord, true);
+        }
+        bits
+    }
+
+    // Returns false if the coord is outside the field
+    pub fn set(&mut self, coord: &Coord, value: bool) -> bool {
+        let Some((x, y)) = self.bounding_box.inside_coords(coord) else {
+            return false;
+        };
+        self.rows.set(y, x, value);
+        self.columns.set(x, y, value);
+        true
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.rows
+            .words
+            .iter()
+            .map(|w| w.count_ones() as usize)
+            .sum()
+    }
+
+    // First set cell met when going from `from` toward `dir`, `from` itself excluded
+    pub fn next_set(&self, from: &Coord, dir: &Direction) -> Option<Coord> {
+        let BoundingBox { xmin, ymin, .. } = self.bounding_box;
+        let (width, height) = (self.bounding_box.width(), self.bounding_box.height());
+        let (x, y) = (from.0 - xmin, from.1 - ymin);
+        let line = |l: isize, len: usize| usize::try_from(l).ok().filter(|l| *l < len);
+        // Position right after `p`, and positions before `p`, as bounds in a line
+        let after = |p: isize| usize::try_from(p + 1).unwrap_or(0);
+        let before = |p: isize, len: usize| usize::try_from(p).map_or(0, |p| p.min(len));
+
+        let (x, y) = match dir {
+            Direction::Ri => (
+                self.rows.first_from(line(y, height)?, after(x))? as isize,
+                y,
+            ),
+            Direction::Le => (
+                self.rows.last_before(line(y, height)?, before(x, width))? as isize,
+                y,
+            ),
+            Direction::Do => (
+                x,
+                self.columns.first_from(line(x, width)?, after(y))? as isize,
+            ),
+            Direction::Up => (
+                x,
+                self.columns
+                    .last_before(line(x, width)?, before(y, height))? as isize,
+            ),
+        };
+        Some(Coord(x + xmin, y + ymin))
+    }
+}
+
+impl Field for BitField {
+    type Out = bool;
+
+    fn get(&self, coords: &Coord) -> Option<&Self::Out> {
+        let (x, y) = self.bounding_box.inside_coords(coords)?;
+        Some(if self.rows.get(y, x) { &true } else { &false })
+    }
+
+    fn definition_area(&self) -> &BoundingBox {
+        &self.bounding_box
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BitField;
+    use crate::space2d::{BoundingBox, Coord, Direction, Field};
+
+    #[test]
+    fn it_scans_rows_and_columns() {
+        // Wide enough for rows to span several words
+        let area = BoundingBox {
+            xmin: -3,
+            xmax: 150,
+            ymin: 0,
+            ymax: 3,
+        };
+        let mut bits = BitField::new(area);
+        [Coord(-3, 1), Coord(70, 1), Coord(140, 1), Coord(70, 2)]
+            .iter()
+            .for_each(|c| assert!(bits.set(c, true)));
+        assert!(!bits.set(&Coord(150, 0), true));
+
+        assert_eq!(bits.count_ones(), 4);
+        assert_eq!(bits.get(&Coord(70, 1)), Some(&true));
+        assert_eq!(bits.get(&Coord(71, 1)), Some(&false));
+        assert_eq!(
+            bits.next_set(&Coord(0, 1), &Direction::Ri),
+            Some(Coord(70, 1))
+        );
+        assert_eq!(
+            bits.next_set(&Coord(70, 1), &Direction::Ri),
+            Some(Coord(140, 1))
+        );
+        assert_eq!(bits.next_set(&Coord(140, 1), &Direction::Ri), None);
+        assert_eq!(
+            bits.next_set(&Coord(200, 1), &Direction::Le),
+            Some(Coord(140, 1))
+        );
+        assert_eq!(
+            bits.next_set(&Coord(70, 1), &Direction::Le),
+            Some(Coord(-3, 1))
+        );
+        assert_eq!(
+            bits.next_set(&Coord(70, -5), &Direction::Do),
+            Some(Coord(70, 1))
+        );
+        assert_eq!(
+            bits.next_set(&Coord(70, 2), &Direction::Up),
+            Some(Coord(70, 1))
+        );
+        assert_eq!(bits.next_set(&Coord(0, 5), &Direction::Ri), None);
+    }
+}
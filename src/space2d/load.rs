@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use super::{Coord, TableField, TableFieldError};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    #[error("Unknown character '{c}' at line {line}, column {column}")]
+    UnknownChar { c: char, line: usize, column: usize },
+    #[error("Line {line} has {found} characters instead of {expected}")]
+    RaggedRow {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[error("Grid too big to be represented")]
+    TooBig,
+}
+
+#[derive(Clone)]
+enum Legend<T> {
+    Cell(T),
+    // Entity standing on a cell with the given value
+    Entity(String, T),
+}
+
+// Builds a field out of a grid of characters, each character being mapped through a legend
+pub struct CharGrid<T> {
+    legend: HashMap<char, Legend<T>>,
+}
+
+// Loaded field and positions of its entities, per name
+pub struct LoadedGrid<T> {
+    pub field: TableField<T>,
+    pub entities: HashMap<String, Vec<Coord>>,
+}
+
+impl<T> LoadedGrid<T> {
+    // Position of the first entity with this name, in reading order
+    pub fn entity(&self, name: &str) -> Option<Coord> {
+        self.entities.get(name)?.first().copied()
+    }
+}
+
+impl<T: Clone> Default for CharGrid<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> CharGrid<T> {
+    pub fn new() -> Self {
+        CharGrid {
+            legend: HashMap::new(),
+        }
+    }
+
+    // The character stands for a cell with this value
+    pub fn cell(mut self, c: char, value: T) -> Self {
+        self.legend.insert(c, Legend::Cell(value));
+        self
+    }
+
+    // The character marks the position of a named entity, standing on a cell with value `under`
+    pub fn entity(mut self, c: char, name: &str, under: T) -> Self {
+        self.legend
+            .insert(c, Legend::Entity(name.to_string(), under));
+        self
+    }
+
+    // Line and columns in errors start at 1
+    pub fn load(&self, input: &str) -> Result<LoadedGrid<T>, LoadError> {
+        let mut entities = HashMap::<String, Vec<Coord>>::new();
+        let mut rows = vec![];
+        for (y, line) in input.lines().enumerate() {
+            let row = line
+                .chars()
+                .enumerate()
+                .map(|(x, c)| match self.legend.get(&c) {
+                    Some(Legend::Cell(value)) => Ok(value.clone()),
+                    Some(Legend::Entity(name, under)) => {
+                        let coord = Coord(x as isize, y as isize);
+                        entities.entry(name.clone()).or_default().push(coord);
+                        Ok(under.clone())
+                    }
+                    None => Err(LoadError::UnknownChar {
+                        c,
+                        line: y + 1,
+                        column: x + 1,
+                    }),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            rows.push(row);
+        }
+
+        let field = TableField::from_rows(rows).map_err(|err| match err {
+            TableFieldError::TableTooBig => LoadError::TooBig,
+            TableFieldError::RaggedRows { line } => LoadError::RaggedRow {
+                line: line + 1,
+                expected: input.lines().next().map(|l| l.chars().count()).unwrap_or(0),
+                found: input
+                    .lines()
+                    .nth(line)
+                    .map(|l| l.chars().count())
+                    .unwrap_or(0),
+            },
+        })?;
+        Ok(LoadedGrid { field, entities })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CharGrid, LoadError};
+    use crate::space2d::{Coord, Field};
+
+    fn legend() -> CharGrid<bool> {
+        CharGrid::new()
+            .cell('.', false)
+            .cell('#', true)
+            .entity('@', "robot", false)
+    }
+
+    #[test]
+    fn it_loads_cells_and_entities() {
+        let grid = legend().load("#.\n.@\n").unwrap();
+        assert_eq!(grid.field.get(&Coord(0, 0)), Some(&true));
+        assert_eq!(grid.field.get(&Coord(1, 1)), Some(&false));
+        assert_eq!(grid.entity("robot"), Some(Coord(1, 1)));
+    }
+
+    #[test]
+    fn it_reports_where_the_grid_is_wrong() {
+        assert_eq!(
+            legend().load("#.\n.X").err(),
+            Some(LoadError::UnknownChar {
+                c: 'X',
+                line: 2,
+                column: 2
+            })
+        );
+        assert_eq!(
+            legend().load("#.\n.@#").err(),
+            Some(LoadError::RaggedRow {
+                line: 2,
+                expected: 2,
+                found: 3
+            })
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use super::view::{Transform, Transformed};
+use super::{Coord, Field, TableField, TableFieldError};
+
+// Small grid of values to look for in a field, None cells being wildcards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern<T> {
+    cells: TableField<Option<T>>,
+}
+
+// Where a pattern was found: the top left cell of the transformed pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    pub anchor: Coord,
+    pub transform: Transform,
+}
+
+impl<T> Pattern<T> {
+    pub fn new(cells: TableField<Option<T>>) -> Self {
+        Pattern { cells }
+    }
+
+    // Each line of the text is a row, `legend` gives the value of each character
+    pub fn from_text(
+        text: &str,
+        legend: impl Fn(char) -> Option<T>,
+    ) -> Result<Self, TableFieldError> {
+        let rows = text
+            .lines()
+            .map(|line| line.chars().map(&legend).collect())
+            .collect();
+        Ok(Pattern::new(TableField::from_rows(rows)?))
+    }
+}
+
+// Pattern as seen through a transform
+struct Variant<T> {
+    transform: Transform,
+    // Cell of the field under the bottom right cell of the pattern, relative to the anchor
+    far_corner: Coord,
+    // Cells which aren't wildcards, relative to the anchor
+    required: Vec<(Coord, T)>,
+}
+
+impl<T: PartialEq> Variant<T> {
+    // The whole pattern must fit in the field
+    fn fits_at<F: Field<Out = T>>(&self, field: &F, anchor: &Coord) -> bool {
+        let area = field.definition_area();
+        area.contains(anchor) && area.contains(&(*anchor + self.far_corner))
+    }
+
+    // Anchors where the variant matches, in reading order
+    fn anchors<F: Field<Out = T>>(&self, field: &F) -> Vec<Coord> {
+        let Some(((first_delta, first), rest)) = self.required.split_first() else {
+            let area = field.definition_area();
+            return area.iter().filter(|a| self.fits_at(field, a)).collect();
+        };
+        // Only cells holding the first required value can be under it, no need to try each anchor
+        field
+            .positions(|value| value == first)
+            .map(|coord| coord - *first_delta)
+            .filter(|anchor| {
+                self.fits_at(field, anchor)
+                    && rest
+                        .iter()
+                        .all(|(delta, expected)| field.get(&(*anchor + *delta)) == Some(expected))
+            })
+            .collect()
+    }
+}
+
+impl<T: Clone + PartialEq> Pattern<T> {
+    // Pattern as seen through each transform, skipping transforms leading to an already seen one
+    fn variants(&self, transforms: &[Transform]) -> Vec<Variant<T>> {
+        let mut seen: Vec<TableField<Option<T>>> = vec![];
+        let mut variants = vec![];
+        for transform in transforms {
+            let cells = Transformed::new(&self.cells, *transform).map(|cell| cell.clone());
+            if seen.contains(&cells) {
+                continue;
+            }
+            let area = cells.definition_area();
+            variants.push(Variant {
+                transform: *transform,
+                far_corner: Coord(area.xmax - 1, area.ymax - 1),
+                required: cells
+                    .iter()
+                    .filter_map(|(coord, cell)| Some((coord, cell.clone()?)))
+                    .collect(),
+            });
+            seen.push(cells);
+        }
+        variants
+    }
+
+    /// Every place where the pattern appears in the field, in reading order of the anchors
+    ///
+    /// * `transforms` Ways the pattern may be rotated or reflected, such as `Transform::ROTATIONS`.
+    ///   Transforms giving the same cells as a previous one are skipped, so symmetric patterns
+    ///   aren't matched several times at the same place
+    pub fn matches<F: Field<Out = T>>(
+        &self,
+        field: &F,
+        transforms: &[Transform],
+    ) -> impl Iterator<Item = Match> {
+        let mut found = vec![];
+        for (i, variant) in self.variants(transforms).iter().enumerate() {
+            found.extend(
+                variant
+                    .anchors(field)
+                    .into_iter()
+                    .map(|anchor| (anchor, i, variant.transform)),
+            );
+        }
+        found.sort_by_key(|(anchor, i, _)| (*anchor, *i));
+        found
+            .into_iter()
+            .map(|(anchor, _, transform)| Match { anchor, transform })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pattern;
+    use crate::space2d::view::Transform;
+    use crate::space2d::{Coord, TableField};
+
+    #[test]
+    fn it_finds_transformed_patterns() {
+        let field = TableField::from_rows(vec![
+            "ab.".chars().collect(),
+            ".ba".chars().collect(),
+            ".ab".chars().collect(),
+        ])
+        .unwrap();
+        let pattern = Pattern::from_text("ab", Some).unwrap();
+
+        let found = pattern.matches(&field, &Transform::ALL).collect::<Vec<_>>();
+        let found = found
+            .iter()
+            .map(|m| (m.anchor, m.transform))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                (Coord(0, 0), Transform::Identity),
+                (Coord(1, 1), Transform::Rotate180),
+                (Coord(1, 1), Transform::Rotate270),
+                (Coord(2, 1), Transform::Rotate90),
+                (Coord(1, 2), Transform::Identity),
+            ]
+        );
+
+        let wildcard = Pattern::from_text("a.\n.a", |c| (c != '.').then_some(c)).unwrap();
+        assert_eq!(wildcard.matches(&field, &[Transform::Identity]).count(), 0);
+        assert_eq!(wildcard.matches(&field, &Transform::ROTATIONS).count(), 1);
+        let anything = Pattern::<char>::from_text("..", |_| None).unwrap();
+        assert_eq!(anything.matches(&field, &Transform::ROTATIONS).count(), 12);
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+use super::{BoundingBox, Coord, Field, TableField};
+
+// Layer drawn over the cells of a field, later layers hiding earlier ones
+pub enum Overlay {
+    // Replace the character of each cell
+    Marks(HashSet<Coord>, char),
+    // Consecutive coords, each one pointing toward the next one with an arrow
+    // The last coord is left untouched so the target stays visible
+    Path(Vec<Coord>),
+    // Fences between two adjacent cells, one of them possibly outside the field
+    Fences(Vec<(Coord, Coord)>),
+}
+
+type CellRender<'a, T> = Box<dyn Fn(&Coord, &T) -> char + 'a>;
+
+// Text rendering of a field, one line per row
+//
+// When fences are drawn, cells are spread apart to leave room for them between each other:
+//
+//  +-+-+
+//  |A A|
+//  + +-+
+//  |A|B|
+//  +-+-+
+pub struct Render<'a, F: Field> {
+    field: &'a F,
+    cell: CellRender<'a, F::Out>,
+    overlays: Vec<Overlay>,
+}
+
+impl<'a, F: Field> Render<'a, F> {
+    pub fn new(field: &'a F, cell: impl Fn(&Coord, &F::Out) -> char + 'a) -> Self {
+        Render {
+            field,
+            cell: Box::new(cell),
+            overlays: vec![],
+        }
+    }
+
+    pub fn overlay(mut self, overlay: Overlay) -> Self {
+        self.overlays.push(overlay);
+        self
+    }
+
+    pub fn marks(self, cells: impl IntoIterator<Item = Coord>, mark: char) -> Self {
+        self.overlay(Overlay::Marks(cells.into_iter().collect(), mark))
+    }
+
+    pub fn path(self, path: impl IntoIterator<Item = Coord>) -> Self {
+        self.overlay(Overlay::Path(path.into_iter().collect()))
+    }
+
+    pub fn fences(self, fences: impl IntoIterator<Item = (Coord, Coord)>) -> Self {
+        self.overlay(Overlay::Fences(fences.into_iter().collect()))
+    }
+
+    fn has_fences(&self) -> bool {
+        self.overlays
+            .iter()
+            .any(|overlay| matches!(overlay, Overlay::Fences(_)))
+    }
+
+    // Characters replacing the rendered cells, and fences to draw
+    fn layers(&self) -> (HashMap<Coord, char>, HashSet<(Coord, Coord)>) {
+        let mut cells = HashMap::new();
+        let mut fences = HashSet::new();
+        for overlay in &self.overlays {
+            match overlay {
+                Overlay::Marks(marked, mark) => {
+                    cells.extend(marked.iter().map(|c| (*c, *mark)));
+                }
+                Overlay::Path(path) => {
+                    cells.extend(path.iter().zip(path.iter().skip(1)).map(|(from, to)| {
+                        let arrow = match (to.0 - from.0, to.1 - from.1) {
+                            (1, 0) => '>',
+                            (-1, 0) => '<',
+                            (0, 1) => 'v',
+                            (0, -1) => '^',
+                            _ => '*',
+                        };
+                        (*from, arrow)
+                    }));
+                }
+                Overlay::Fences(between) => {
+                    fences.extend(between.iter().flat_map(|(a, b)| [(*a, *b), (*b, *a)]));
+                }
+            }
+        }
+        (cells, fences)
+    }
+
+    fn cell_char(&self, cells: &HashMap<Coord, char>, coord: &Coord) -> char {
+        cells.get(coord).copied().unwrap_or_else(|| {
+            self.field
+                .get(coord)
+                .map(|value| (self.cell)(coord, value))
+                .unwrap_or(' ')
+        })
+    }
+
+    pub fn render(&self) -> String {
+        let (cells, fences) = self.layers();
+        let BoundingBox {
+            xmin,
+            xmax,
+            ymin,
+            ymax,
+        } = *self.field.definition_area();
+
+        let mut out = String::new();
+        if !self.has_fences() {
+            for y in ymin..ymax {
+                out.extend((xmin..xmax).map(|x| self.cell_char(&cells, &Coord(x, y))));
+                out.push('\n');
+            }
+            return out;
+        }
+
+        // Spread coords: cell (x, y) is drawn at (2x + 1, 2y + 1) relative to the box corner
+        let fenced = |a: Coord, b: Coord| fences.contains(&(a, b));
+        for sy in 0..=2 * (ymax - ymin) {
+            for sx in 0..=2 * (xmax - xmin) {
+                let (x, y) = (xmin + sx / 2, ymin + sy / 2);
+                let c = match (sx % 2, sy % 2) {
+                    (1, 1) => self.cell_char(&cells, &Coord(x, y)),
+                    (0, 1) => match fenced(Coord(x - 1, y), Coord(x, y)) {
+                        true => '|',
+                        false => ' ',
+                    },
+                    (1, 0) => match fenced(Coord(x, y - 1), Coord(x, y)) {
+                        true => '-',
+                        false => ' ',
+                    },
+                    _ => {
+                        let touching = fenced(Coord(x - 1, y - 1), Coord(x, y - 1))
+                            || fenced(Coord(x - 1, y), Coord(x, y))
+                            || fenced(Coord(x - 1, y - 1), Coord(x - 1, y))
+                            || fenced(Coord(x, y - 1), Coord(x, y));
+                        match touching {
+                            true => '+',
+                            false => ' ',
+                        }
+                    }
+                };
+                out.push(c);
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl<F: Field> Display for Render<'_, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render())
+    }
+}
+
+// Render each cell of the field with a single character
+pub fn render_with<F: Field>(field: &F, cell: impl Fn(&Coord, &F::Out) -> char) -> String {
+    Render::new(field, cell).render()
+}
+
+impl<T: Display> Display for TableField<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bb = self.definition_area();
+        for y in bb.ymin..bb.ymax {
+            for x in bb.xmin..bb.xmax {
+                if let Some(value) = self.get(&Coord(x, y)) {
+                    write!(f, "{value}")?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::{render_with, Render};
+    use crate::space2d::{Coord, TableField};
+
+    fn garden() -> TableField<char> {
+        TableField::from_rows(vec![vec!['A', 'A'], vec!['A', 'B']]).unwrap()
+    }
+
+    #[test]
+    fn it_renders_cells_and_overlays() {
+        let garden = garden();
+        assert_eq!(garden.to_string(), "AA\nAB\n");
+        assert_eq!(
+            render_with(&garden, |c, v| if c.0 == 0 { '.' } else { *v }),
+            ".A\n.B\n"
+        );
+        let rendered = Render::new(&garden, |_, v| *v)
+            .path([Coord(0, 0), Coord(1, 0), Coord(1, 1)])
+            .marks([Coord(0, 1)], '#')
+            .render();
+        assert_eq!(rendered, ">v\n#B\n");
+    }
+
+    #[test]
+    fn it_renders_fences_between_cells() {
+        let garden = garden();
+        let fences = [
+            (Coord(0, 0), Coord(0, -1)),
+            (Coord(1, 0), Coord(1, -1)),
+            (Coord(0, 0), Coord(-1, 0)),
+            (Coord(0, 1), Coord(-1, 1)),
+            (Coord(1, 0), Coord(2, 0)),
+            (Coord(1, 0), Coord(1, 1)),
+            (Coord(0, 1), Coord(1, 1)),
+            (Coord(1, 1), Coord(2, 1)),
+            (Coord(0, 1), Coord(0, 2)),
+            (Coord(1, 1), Coord(1, 2)),
+        ];
+        let expected = indoc! {"
+            +-+-+
+            |A A|
+            + +-+
+            |A|B|
+            +-+-+
+        "};
+        assert_eq!(
+            Render::new(&garden, |_, v| *v).fences(fences).render(),
+            expected
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use super::{BoundingBox, Coord, Field, FieldMut};
+
+// The eight symmetries of a rectangle, rotations being clockwise
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Transform {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+    Transpose,
+    AntiTranspose,
+}
+
+impl Transform {
+    pub const ROTATIONS: [Transform; 4] = [
+        Transform::Identity,
+        Transform::Rotate90,
+        Transform::Rotate180,
+        Transform::Rotate270,
+    ];
+
+    pub const ALL: [Transform; 8] = [
+        Transform::Identity,
+        Transform::Rotate90,
+        Transform::Rotate180,
+        Transform::Rotate270,
+        Transform::FlipHorizontal,
+        Transform::FlipVertical,
+        Transform::Transpose,
+        Transform::AntiTranspose,
+    ];
+
+    pub fn swaps_axes(&self) -> bool {
+        matches!(
+            self,
+            Transform::Rotate90
+                | Transform::Rotate270
+                | Transform::Transpose
+                | Transform::AntiTranspose
+        )
+    }
+
+    // Size of the transformed box, for a box of size (w, h)
+    pub fn size(&self, (w, h): (usize, usize)) -> (usize, usize) {
+        if self.swaps_axes() {
+            (h, w)
+        } else {
+            (w, h)
+        }
+    }
+
+    // Position in the original box of size (w, h) of the cell at `coord` in the transformed one,
+    // both boxes starting at Coord(0, 0)
+    pub fn source_of(&self, coord: &Coord, (w, h): (usize, usize)) -> Coord {
+        let (w, h) = (w as isize, h as isize);
+        let Coord(u, v) = *coord;
+        match self {
+            Transform::Identity => Coord(u, v),
+            Transform::Rotate90 => Coord(v, h - 1 - u),
+            Transform::Rotate180 => Coord(w - 1 - u, h - 1 - v),
+            Transform::Rotate270 => Coord(w - 1 - v, u),
+            Transform::FlipHorizontal => Coord(w - 1 - u, v),
+            Transform::FlipVertical => Coord(u, h - 1 - v),
+            Transform::Transpose => Coord(v, u),
+            Transform::AntiTranspose => Coord(w - 1 - v, h - 1 - u),
+        }
+    }
+}
+
+// Field seen through a symmetry, its definition area starting at Coord(0, 0)
+pub struct Transformed<F: Field> {
+    inner: F,
+    transform: Transform,
+    area: BoundingBox,
+}
+
+impl<F: Field> Transformed<F> {
+    pub fn new(inner: F, transform: Transform) -> Self {
+        let inner_area = inner.definition_area();
+        let (w, h) = transform.size((inner_area.width(), inner_area.height()));
+        Transformed {
+            area: BoundingBox {
+                xmin: 0,
+                xmax: w as isize,
+                ymin: 0,
+                ymax: h as isize,
+            },
+            inner,
+            transform,
+        }
+    }
+
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+
+    // Coord in the inner field of the cell seen at `coord`
+    pub fn source_of(&self, coord: &Coord) -> Option<Coord> {
+        let inner_area = self.inner.definition_area();
+        self.area.contains(coord).then(|| {
+            let size = (inner_area.width(), inner_area.height());
+            self.transform.source_of(coord, size) + Coord(inner_area.xmin, inner_area.ymin)
+        })
+    }
+}
+
+impl<F: Field> Field for Transformed<F> {
+    type Out = F::Out;
+
+    fn get(&self, coords: &Coord) -> Option<&Self::Out> {
+        self.inner.get(&self.source_of(coords)?)
+    }
+
+    fn definition_area(&self) -> &BoundingBox {
+        &self.area
+    }
+}
+
+impl<F: FieldMut> FieldMut for Transformed<F> {
+    fn get_mut(&mut self, coords: &Coord) -> Option<&mut Self::Out> {
+        let source = self.source_of(coords)?;
+        self.inner.get_mut(&source)
+    }
+}
+
+// Part of a field, keeping the coords of the inner field
+pub struct Window<F: Field> {
+    inner: F,
+    area: BoundingBox,
+}
+
+impl<F: Field> Window<F> {
+    // The window is cropped to the definition area of the inner field
+    pub fn new(inner: F, window: BoundingBox) -> Self {
+        let area = inner
+            .definition_area()
+            .intersection(&window)
+            .unwrap_or(BoundingBox::from_points([]));
+        Window { inner, area }
+    }
+}
+
+impl<F: Field> Field for Window<F> {
+    type Out = F::Out;
+
+    fn get(&self, coords: &Coord) -> Option<&Self::Out> {
+        self.area.contains(coords).then(|| self.inner.get(coords))?
+    }
+
+    fn definition_area(&self) -> &BoundingBox {
+        &self.area
+    }
+}
+
+impl<F: FieldMut> FieldMut for Window<F> {
+    fn get_mut(&mut self, coords: &Coord) -> Option<&mut Self::Out> {
+        if self.area.contains(coords) {
+            self.inner.get_mut(coords)
+        } else {
+            None
+        }
+    }
+}
+
+// Field stretched by integer factors, each inner cell being seen as a block of cells
+//
+// Cells of a block share the same inner value, so mutating one of them mutates the whole block.
+pub struct Scaled<F: Field> {
+    inner: F,
+    factors: (usize, usize),
+    area: BoundingBox,
+}
+
+impl<F: Field> Scaled<F> {
+    // Factors must not be 0
+    pub fn new(inner: F, horizontal: usize, vertical: usize) -> Self {
+        let (sx, sy) = (horizontal as isize, vertical as isize);
+        let inner_area = *inner.definition_area();
+        Scaled {
+            area: BoundingBox {
+                xmin: inner_area.xmin * sx,
+                xmax: inner_area.xmax * sx,
+                ymin: inner_area.ymin * sy,
+                ymax: inner_area.ymax * sy,
+            },
+            inner,
+            factors: (horizontal, vertical),
+        }
+    }
+
+    pub fn source_of(&self, coord: &Coord) -> Coord {
+        Coord(
+            coord.0.div_euclid(self.factors.0 as isize),
+            coord.1.div_euclid(self.factors.1 as isize),
+        )
+    }
+}
+
+impl<F: Field> Field for Scaled<F> {
+    type Out = F::Out;
+
+    fn get(&self, coords: &Coord) -> Option<&Self::Out> {
+        self.inner.get(&self.source_of(coords))
+    }
+
+    fn definition_area(&self) -> &BoundingBox {
+        &self.area
+    }
+}
+
+impl<F: FieldMut> FieldMut for Scaled<F> {
+    fn get_mut(&mut self, coords: &Coord) -> Option<&mut Self::Out> {
+        let source = self.source_of(coords);
+        self.inner.get_mut(&source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Scaled, Transform, Transformed, Window};
+    use crate::space2d::render::render_with;
+    use crate::space2d::{BoundingBox, Coord, FieldMut, TableField};
+
+    fn abc() -> TableField<char> {
+        TableField::from_rows(vec![vec!['a', 'b', 'c'], vec!['d', 'e', 'f']]).unwrap()
+    }
+
+    #[test]
+    fn it_sees_fields_through_symmetries() {
+        let field = abc();
+        let seen = |transform| render_with(&Transformed::new(&field, transform), |_, c| *c);
+        assert_eq!(seen(Transform::Identity), "abc\ndef\n");
+        assert_eq!(seen(Transform::Rotate90), "da\neb\nfc\n");
+        assert_eq!(seen(Transform::Rotate180), "fed\ncba\n");
+        assert_eq!(seen(Transform::Rotate270), "cf\nbe\nad\n");
+        assert_eq!(seen(Transform::FlipHorizontal), "cba\nfed\n");
+        assert_eq!(seen(Transform::FlipVertical), "def\nabc\n");
+        assert_eq!(seen(Transform::Transpose), "ad\nbe\ncf\n");
+        assert_eq!(seen(Transform::AntiTranspose), "fc\neb\nda\n");
+    }
+
+    #[test]
+    fn it_crops_and_scales() {
+        let field = abc();
+        let window = Window::new(
+            &field,
+            BoundingBox {
+                xmin: 1,
+                xmax: 5,
+                ymin: 1,
+                ymax: 2,
+            },
+        );
+        assert_eq!(render_with(&window, |_, c| *c), "ef\n");
+        assert_eq!(
+            render_with(&Scaled::new(&window, 2, 1), |_, c| *c),
+            "eeff\n"
+        );
+    }
+
+    #[test]
+    fn it_mutates_the_inner_field() {
+        let mut field = abc();
+        let mut rotated = Transformed::new(&mut field, Transform::Rotate90);
+        *rotated.get_mut(&Coord(1, 0)).unwrap() = 'x';
+        let mut window = Window::new(&mut field, BoundingBox::from_points([Coord(2, 1)]));
+        assert_eq!(window.get_mut(&Coord(1, 1)), None);
+        *window.get_mut(&Coord(2, 1)).unwrap() = 'y';
+        assert_eq!(render_with(&field, |_, c| *c), "xbc\ndey\n");
+    }
+}
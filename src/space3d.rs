@@ -0,0 +1,470 @@
+use derive_more::derive::{Add, Sub};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::ops::Mul;
+
+use crate::explore_2d::{Position, Space};
+use crate::relation::Relation;
+
+#[derive(Debug, Copy, Clone, Add, Sub, Hash, PartialEq, Eq)]
+pub struct Coord3(pub isize, pub isize, pub isize);
+
+impl Coord3 {
+    pub fn manhattan(&self, other: &Coord3) -> usize {
+        self.0.abs_diff(other.0) + self.1.abs_diff(other.1) + self.2.abs_diff(other.2)
+    }
+
+    pub fn chebyshev(&self, other: &Coord3) -> usize {
+        self.0
+            .abs_diff(other.0)
+            .max(self.1.abs_diff(other.1))
+            .max(self.2.abs_diff(other.2))
+    }
+}
+
+impl Mul<isize> for Coord3 {
+    type Output = Coord3;
+
+    fn mul(self, rhs: isize) -> Coord3 {
+        Coord3(self.0 * rhs, self.1 * rhs, self.2 * rhs)
+    }
+}
+
+// Layer by layer, each one in reading order, as `BoundingBox3::iter`
+impl Ord for Coord3 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.2, self.1, self.0).cmp(&(other.2, other.1, other.0))
+    }
+}
+
+impl PartialOrd for Coord3 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Position for Coord3 {
+    const STILL: Coord3 = Coord3(0, 0, 0);
+}
+
+impl From<(isize, isize, isize)> for Coord3 {
+    fn from((x, y, z): (isize, isize, isize)) -> Self {
+        Coord3(x, y, z)
+    }
+}
+
+pub mod neighbors {
+    use super::Coord3;
+
+    // Sharing a face
+    pub static SIX: [Coord3; 6] = [
+        Coord3(1, 0, 0),
+        Coord3(-1, 0, 0),
+        Coord3(0, 1, 0),
+        Coord3(0, -1, 0),
+        Coord3(0, 0, 1),
+        Coord3(0, 0, -1),
+    ];
+
+    // Sharing a face or an edge
+    pub fn eighteen() -> Vec<Coord3> {
+        twenty_six()
+            .into_iter()
+            .filter(|d| d.manhattan(&Coord3(0, 0, 0)) <= 2)
+            .collect()
+    }
+
+    // Sharing a face, an edge or a corner
+    pub fn twenty_six() -> Vec<Coord3> {
+        (-1..=1)
+            .flat_map(|z| (-1..=1).flat_map(move |y| (-1..=1).map(move |x| Coord3(x, y, z))))
+            .filter(|d| *d != Coord3(0, 0, 0))
+            .collect()
+    }
+}
+
+// Max values are excluded
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct BoundingBox3 {
+    pub xmin: isize,
+    pub xmax: isize,
+    pub ymin: isize,
+    pub ymax: isize,
+    pub zmin: isize,
+    pub zmax: isize,
+}
+
+impl BoundingBox3 {
+    pub fn contains(&self, coord: &Coord3) -> bool {
+        (self.xmin..self.xmax).contains(&coord.0)
+            && (self.ymin..self.ymax).contains(&coord.1)
+            && (self.zmin..self.zmax).contains(&coord.2)
+    }
+
+    pub fn inside_coords(&self, coord: &Coord3) -> Option<(usize, usize, usize)> {
+        self.contains(coord).then_some((
+            (coord.0 - self.xmin) as usize,
+            (coord.1 - self.ymin) as usize,
+            (coord.2 - self.zmin) as usize,
+        ))
+    }
+
+    // Smallest box containing all the points, empty box at the origin if there is none
+    pub fn from_points(points: impl IntoIterator<Item = Coord3>) -> BoundingBox3 {
+        points
+            .into_iter()
+            .map(|c| BoundingBox3 {
+                xmin: c.0,
+                xmax: c.0 + 1,
+                ymin: c.1,
+                ymax: c.1 + 1,
+                zmin: c.2,
+                zmax: c.2 + 1,
+            })
+            .reduce(|a, b| a.union(&b))
+            .unwrap_or(BoundingBox3 {
+                xmin: 0,
+                xmax: 0,
+                ymin: 0,
+                ymax: 0,
+                zmin: 0,
+                zmax: 0,
+            })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.xmin >= self.xmax || self.ymin >= self.ymax || self.zmin >= self.zmax
+    }
+
+    pub fn width(&self) -> usize {
+        (self.xmax - self.xmin).max(0) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.ymax - self.ymin).max(0) as usize
+    }
+
+    pub fn depth(&self) -> usize {
+        (self.zmax - self.zmin).max(0) as usize
+    }
+
+    pub fn volume(&self) -> usize {
+        self.width() * self.height() * self.depth()
+    }
+
+    // Smallest box containing both, empty boxes being ignored
+    pub fn union(&self, other: &BoundingBox3) -> BoundingBox3 {
+        match (self.is_empty(), other.is_empty()) {
+            (_, true) => *self,
+            (true, false) => *other,
+            (false, false) => BoundingBox3 {
+                xmin: self.xmin.min(other.xmin),
+                xmax: self.xmax.max(other.xmax),
+                ymin: self.ymin.min(other.ymin),
+                ymax: self.ymax.max(other.ymax),
+                zmin: self.zmin.min(other.zmin),
+                zmax: self.zmax.max(other.zmax),
+            },
+        }
+    }
+
+    // Grow the box by `n` cells on every side
+    pub fn expand(&self, n: usize) -> BoundingBox3 {
+        let n = n as isize;
+        BoundingBox3 {
+            xmin: self.xmin - n,
+            xmax: self.xmax + n,
+            ymin: self.ymin - n,
+            ymax: self.ymax + n,
+            zmin: self.zmin - n,
+            zmax: self.zmax + n,
+        }
+    }
+
+    // Layer by layer, each one in reading order
+    pub fn iter(&self) -> impl Iterator<Item = Coord3> {
+        let BoundingBox3 {
+            xmin,
+            xmax,
+            ymin,
+            ymax,
+            zmin,
+            zmax,
+        } = *self;
+        (zmin..zmax).flat_map(move |z| {
+            (ymin..ymax).flat_map(move |y| (xmin..xmax).map(move |x| Coord3(x, y, z)))
+        })
+    }
+}
+
+pub trait Field3 {
+    type Out;
+    fn get(&self, coords: &Coord3) -> Option<&Self::Out>;
+    fn get_mut(&mut self, coords: &Coord3) -> Option<&mut Self::Out>;
+    fn definition_area(&self) -> &BoundingBox3;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableField3<T> {
+    bounding_box: BoundingBox3,
+    values: Vec<T>,
+}
+
+impl<T> TableField3<T> {
+    pub fn from_fn(bounding_box: BoundingBox3, f: impl FnMut(Coord3) -> T) -> Self {
+        TableField3 {
+            values: bounding_box.iter().map(f).collect(),
+            bounding_box,
+        }
+    }
+
+    fn index(&self, coords: &Coord3) -> Option<usize> {
+        let (w, h) = (self.bounding_box.width(), self.bounding_box.height());
+        self.bounding_box
+            .inside_coords(coords)
+            .map(|(x, y, z)| (z * h + y) * w + x)
+    }
+}
+
+impl<T: Clone> TableField3<T> {
+    pub fn filled(bounding_box: BoundingBox3, value: T) -> Self {
+        Self::from_fn(bounding_box, |_| value.clone())
+    }
+}
+
+impl<T> Field3 for TableField3<T> {
+    type Out = T;
+
+    fn get(&self, coords: &Coord3) -> Option<&Self::Out> {
+        self.index(coords).map(|i| &self.values[i])
+    }
+
+    fn get_mut(&mut self, coords: &Coord3) -> Option<&mut Self::Out> {
+        self.index(coords).map(|i| &mut self.values[i])
+    }
+
+    fn definition_area(&self) -> &BoundingBox3 {
+        &self.bounding_box
+    }
+}
+
+impl<T> Space for TableField3<T> {
+    type Coord = Coord3;
+    type Out = T;
+
+    fn cell(&self, coord: &Coord3) -> Option<&T> {
+        self.get(coord)
+    }
+
+    fn canonical(&self, coord: &Coord3) -> Option<Coord3> {
+        self.bounding_box.contains(coord).then_some(*coord)
+    }
+
+    // Values are already stored layer by layer
+    fn cells(&self) -> impl Iterator<Item = (Coord3, &T)> {
+        self.bounding_box.iter().zip(self.values.iter())
+    }
+}
+
+// Field storing only the values different from a default one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseField3<T> {
+    bounding_box: BoundingBox3,
+    default: T,
+    values: HashMap<Coord3, T>,
+}
+
+impl<T> SparseField3<T> {
+    pub fn new(bounding_box: BoundingBox3, default: T) -> Self {
+        SparseField3 {
+            bounding_box,
+            default,
+            values: HashMap::new(),
+        }
+    }
+
+    // Field whose definition area is the smallest one containing all the given values
+    pub fn from_values(values: impl IntoIterator<Item = (Coord3, T)>, default: T) -> Self {
+        let values: HashMap<_, _> = values.into_iter().collect();
+        SparseField3 {
+            bounding_box: BoundingBox3::from_points(values.keys().copied()),
+            default,
+            values,
+        }
+    }
+
+    // Store a value inside the definition area, giving it back as an error outside of it
+    pub fn insert(&mut self, coord: Coord3, value: T) -> Result<Option<T>, T> {
+        if !self.bounding_box.contains(&coord) {
+            return Err(value);
+        }
+        Ok(self.values.insert(coord, value))
+    }
+
+    // Store a value, growing the definition area if the coord lies outside of it
+    pub fn insert_growing(&mut self, coord: Coord3, value: T) -> Option<T> {
+        self.bounding_box = self.bounding_box.union(&BoundingBox3::from_points([coord]));
+        self.values.insert(coord, value)
+    }
+
+    pub fn remove(&mut self, coord: &Coord3) -> Option<T> {
+        self.values.remove(coord)
+    }
+
+    pub fn stored(&self) -> impl Iterator<Item = (&Coord3, &T)> {
+        self.values.iter()
+    }
+}
+
+impl<T: Clone> Field3 for SparseField3<T> {
+    type Out = T;
+
+    fn get(&self, coords: &Coord3) -> Option<&Self::Out> {
+        self.bounding_box
+            .contains(coords)
+            .then(|| self.values.get(coords).unwrap_or(&self.default))
+    }
+
+    // Reading a default value through a mutable borrow stores it
+    fn get_mut(&mut self, coords: &Coord3) -> Option<&mut Self::Out> {
+        self.bounding_box
+            .contains(coords)
+            .then(|| self.values.entry(*coords).or_insert(self.default.clone()))
+    }
+
+    fn definition_area(&self) -> &BoundingBox3 {
+        &self.bounding_box
+    }
+}
+
+impl<T: Clone> Space for SparseField3<T> {
+    type Coord = Coord3;
+    type Out = T;
+
+    fn cell(&self, coord: &Coord3) -> Option<&T> {
+        self.get(coord)
+    }
+
+    fn canonical(&self, coord: &Coord3) -> Option<Coord3> {
+        self.bounding_box.contains(coord).then_some(*coord)
+    }
+
+    fn cells(&self) -> impl Iterator<Item = (Coord3, &T)> {
+        self.bounding_box
+            .iter()
+            .map(|coord| (coord, self.values.get(&coord).unwrap_or(&self.default)))
+    }
+}
+
+// Cells connected to `start`, `start` included, without recursion
+pub fn flood_fill<F: Field3>(
+    field: &F,
+    start: Coord3,
+    neighbourhood: &[Coord3],
+    connected: impl Relation<F::Out>,
+) -> HashSet<Coord3> {
+    let mut filled = HashSet::new();
+    if field.get(&start).is_none() {
+        return filled;
+    }
+    filled.insert(start);
+    let mut stack = vec![start];
+    while let Some(coord) = stack.pop() {
+        let Some(value) = field.get(&coord) else {
+            continue;
+        };
+        for delta in neighbourhood {
+            let neighbour = coord + *delta;
+            let joined = field
+                .get(&neighbour)
+                .is_some_and(|other| connected.relates((value, other)));
+            if joined && filled.insert(neighbour) {
+                stack.push(neighbour);
+            }
+        }
+    }
+    filled
+}
+
+// Number of faces of the cells not shared with another cell of the set
+pub fn surface_area(cells: &HashSet<Coord3>) -> usize {
+    cells
+        .iter()
+        .flat_map(|cell| neighbors::SIX.iter().map(move |delta| *cell + *delta))
+        .filter(|neighbour| !cells.contains(neighbour))
+        .count()
+}
+
+// Surface reachable from outside the cells, leaving out faces of inner cavities
+pub fn exterior_surface_area(cells: &HashSet<Coord3>) -> usize {
+    let area = BoundingBox3::from_points(cells.iter().copied()).expand(1);
+    let space = TableField3::from_fn(area, |coord| cells.contains(&coord));
+    let start = Coord3(area.xmin, area.ymin, area.zmin);
+    let outside = flood_fill(&space, start, &neighbors::SIX, |(a, b): (&bool, &bool)| {
+        !*a && !*b
+    });
+    cells
+        .iter()
+        .flat_map(|cell| neighbors::SIX.iter().map(move |delta| *cell + *delta))
+        .filter(|neighbour| outside.contains(neighbour))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{exterior_surface_area, neighbors, surface_area, Coord3};
+    use super::{BoundingBox3, Field3, SparseField3};
+    use itertools::Itertools;
+    use std::collections::HashSet;
+
+    #[test]
+    fn it_computes_surfaces() {
+        assert_eq!(neighbors::eighteen().len(), 18);
+        assert_eq!(neighbors::twenty_six().len(), 26);
+
+        let two = HashSet::from([Coord3(1, 1, 1), Coord3(2, 1, 1)]);
+        assert_eq!(surface_area(&two), 10);
+
+        // Hollow 3x3x3 cube
+        let hollow = (0..27)
+            .map(|i| Coord3(i % 3, i / 3 % 3, i / 9))
+            .filter(|c| *c != Coord3(1, 1, 1))
+            .collect::<HashSet<_>>();
+        assert_eq!(surface_area(&hollow), 54 + 6);
+        assert_eq!(exterior_surface_area(&hollow), 54);
+    }
+
+    #[test]
+    fn it_orders_coords_layer_by_layer() {
+        let area = BoundingBox3::from_points([Coord3(0, 0, 0), Coord3(1, 1, 1)]);
+        assert!(area.iter().tuple_windows().all(|(a, b)| a < b));
+        assert!(Coord3(5, 5, 0) < Coord3(0, 0, 1));
+        assert!(Coord3(5, 0, 1) < Coord3(0, 1, 1));
+    }
+
+    #[test]
+    fn it_grows_sparse_fields() {
+        let mut sparse = SparseField3::from_values([(Coord3(1, 0, 2), 'a')], '.');
+        assert_eq!(sparse.definition_area().volume(), 1);
+        assert_eq!(sparse.insert(Coord3(-1, 2, 2), 'b'), Err('b'));
+        assert_eq!(sparse.insert_growing(Coord3(-1, 2, 2), 'b'), None);
+        let grown = BoundingBox3 {
+            xmin: -1,
+            xmax: 2,
+            ymin: 0,
+            ymax: 3,
+            zmin: 2,
+            zmax: 3,
+        };
+        assert_eq!(sparse.definition_area(), &grown);
+        assert_eq!(sparse.get(&Coord3(0, 1, 2)), Some(&'.'));
+        assert_eq!(sparse.get(&Coord3(0, 1, 3)), None);
+        assert_eq!(sparse.insert(Coord3(-1, 2, 2), 'c'), Ok(Some('b')));
+
+        // Reading a default value mutably stores it
+        *sparse.get_mut(&Coord3(0, 1, 2)).unwrap() = 'c';
+        assert_eq!(sparse.stored().count(), 3);
+        assert_eq!(sparse.remove(&Coord3(1, 0, 2)), Some('a'));
+        assert_eq!(sparse.get(&Coord3(1, 0, 2)), Some(&'.'));
+    }
+}